@@ -5,8 +5,11 @@ use unicode_segmentation::UnicodeSegmentation;
 use crate::{
     config::Config,
     document::Document,
+    kinsoku_checker::KinsokuChecker,
     line_break::{BreakPoint, LineBreaker},
+    normalize_checker::check_normalization,
     spacing_checker::SpacingChecker,
+    suppression::Suppressions,
 };
 
 pub(crate) fn check_one_file(
@@ -20,10 +23,22 @@ pub(crate) fn check_one_file(
         anyhow::bail!("the document passed to check_one_file does not have CST.");
     };
 
+    // Honour the suppression comments in the document
+    let suppressions = Suppressions::new(&document.content, tree)?;
+    if suppressions.ignores_file() {
+        return Ok(diagnostics);
+    }
+
     // Initialize required components
     let breaker = LineBreaker::builder()
         .ambiguous_width(config.ambiguous_width)
         .max_width(config.max_width)
+        .prohibited_start(config.line_break.prohibited_start())
+        .prohibited_end(config.line_break.prohibited_end())
+        .hanging_punctuation(config.line_break.hanging_punctuation)
+        .strategy(config.line_break.strategy)
+        .push_in_tolerance(config.line_break.push_in_tolerance)
+        .inseparable(&config.line_break.inseparable)
         .build()?;
 
     // Check line length problems
@@ -38,6 +53,16 @@ pub(crate) fn check_one_file(
     spacing_checker.walk(tree);
     diagnostics.extend(spacing_checker.diagnostics().iter().cloned());
 
+    // Check line breaks violating the kinsoku rule
+    let mut kinsoku_checker = KinsokuChecker::new(config, document);
+    kinsoku_checker.walk(tree);
+    diagnostics.extend(kinsoku_checker.diagnostics().iter().cloned());
+
+    // Check full-width and half-width variants of characters to normalize
+    diagnostics.extend(check_normalization(config, document, tree)?);
+
+    diagnostics
+        .retain(|diagnostic| !suppressions.is_suppressed(diagnostic.start.line, &diagnostic.code));
     Ok(diagnostics)
 }
 
@@ -1,5 +1,6 @@
 use std::{collections::BTreeMap, path::PathBuf};
 
+use cjkfmt_core::position::Position;
 use clap::{Parser, Subcommand, ValueEnum};
 use figment::{
     Profile, Provider,
@@ -7,7 +8,10 @@ use figment::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::config::{AmbiguousWidth, SpacingRule};
+use crate::config::{
+    AmbiguousWidth, CharacterSet, Comma, FullwidthAlphanumerics, FullwidthSpace, HalfwidthKatakana,
+    KinsokuPreset, KinsokuStrategy, LineEnding, Parentheses, Period, SpacingRule, WrapMode,
+};
 
 #[derive(ValueEnum, Debug, Clone, Deserialize, Serialize)]
 pub enum ColorOutputMode {
@@ -16,6 +20,26 @@ pub enum ColorOutputMode {
     Auto,
 }
 
+/// Format of the diagnostics reported by the `check` command.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum OutputFormat {
+    /// Human-readable `file:line:column: CODE message` lines.
+    #[default]
+    Text,
+
+    /// A JSON array of diagnostics.
+    Json,
+
+    /// A JSON object per diagnostic, one per line.
+    Jsonl,
+
+    /// A SARIF 2.1.0 log for code scanning tools.
+    Sarif,
+
+    /// GitHub Actions workflow commands that annotate pull requests.
+    Github,
+}
+
 #[derive(Parser, Debug, Deserialize, Serialize)]
 #[command(version, about, long_about = None)]
 pub struct CliArgs {
@@ -37,6 +61,14 @@ pub struct CliArgs {
     #[arg(long, value_enum)]
     pub ambiguous_width: Option<AmbiguousWidth>,
 
+    /// How to wrap lines of Markdown paragraphs: `split` or `reflow`. [default: split]
+    #[arg(long, value_enum)]
+    pub wrap: Option<WrapMode>,
+
+    /// Line ending to write when formatting: `auto`, `lf`, `crlf`, or `native`. [default: auto]
+    #[arg(long, value_enum)]
+    pub line_ending: Option<LineEnding>,
+
     /// Require, prohibit, or ignore spaces between full-width and half-width alphabets. [default: ignore]
     #[arg(long, value_enum)]
     pub spacing_alphabets: Option<SpacingRule>,
@@ -45,6 +77,88 @@ pub struct CliArgs {
     #[arg(long, value_enum)]
     pub spacing_digits: Option<SpacingRule>,
 
+    /// Treat full-width punctuation as full-width characters in spacing rules. [default: false]
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    pub spacing_punctuation_as_fullwidth: Option<bool>,
+
+    /// Spacing rule between code spans and full-width characters: `require`, `prohibit`, or `ignore`. [default: ignore]
+    #[arg(long, value_enum)]
+    pub spacing_inline_code: Option<SpacingRule>,
+
+    /// Apply the spacing rules across emphasis delimiters and link syntax. [default: false]
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    pub spacing_markup_boundaries: Option<bool>,
+
+    /// How to handle full-width alphabets and digits: `keep` or `to_halfwidth`. [default: keep]
+    #[arg(long, value_enum)]
+    pub normalize_fullwidth_alphanumerics: Option<FullwidthAlphanumerics>,
+
+    /// How to handle half-width katakana: `keep` or `to_fullwidth`. [default: keep]
+    #[arg(long, value_enum)]
+    pub normalize_halfwidth_katakana: Option<HalfwidthKatakana>,
+
+    /// How to handle full-width spaces: `keep` or `to_ascii`. [default: keep]
+    #[arg(long, value_enum)]
+    pub normalize_fullwidth_space: Option<FullwidthSpace>,
+
+    /// Full stop to use: `keep`, `ideographic` (`。`), or `fullwidth` (`．`). [default: keep]
+    #[arg(long, value_enum)]
+    pub punctuation_period: Option<Period>,
+
+    /// Comma to use: `keep`, `ideographic` (`、`), or `fullwidth` (`，`). [default: keep]
+    #[arg(long, value_enum)]
+    pub punctuation_comma: Option<Comma>,
+
+    /// How to handle parentheses around CJK text: `keep` or `fullwidth_around_cjk`. [default: keep]
+    #[arg(long, value_enum)]
+    pub punctuation_parentheses: Option<Parentheses>,
+
+    /// Sets of characters prohibited at the start and the end of a line: `standard`, `strict`, or `loose`. [default: standard]
+    #[arg(long, value_enum)]
+    pub line_break_preset: Option<KinsokuPreset>,
+
+    /// Characters prohibited at the start of a line, replacing the preset's set.
+    #[arg(long, value_name = "CHARACTERS")]
+    pub line_break_prohibited_start: Option<String>,
+
+    /// Characters prohibited at the end of a line, replacing the preset's set.
+    #[arg(long, value_name = "CHARACTERS")]
+    pub line_break_prohibited_end: Option<String>,
+
+    /// Let a full stop or a comma hang past the maximum width instead of wrapping before it. [default: false]
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    pub line_break_hanging_punctuation: Option<bool>,
+
+    /// How to resolve a line break prohibited by the kinsoku rule: `push_out`, `push_in`, or `min_raggedness`. [default: push_out]
+    #[arg(long, value_enum)]
+    pub line_break_strategy: Option<KinsokuStrategy>,
+
+    /// How far characters pushed in to a line may exceed the maximum width. [default: 2]
+    #[arg(long, value_name = "WIDTH")]
+    pub line_break_push_in_tolerance: Option<u32>,
+
+    /// A sequence which must not be split across lines. Can be given multiple times.
+    #[arg(long, value_name = "SEQUENCE")]
+    pub line_break_inseparable: Vec<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -66,6 +180,12 @@ impl Provider for CliArgs {
                 Value::serialize(ambiguous_width)?,
             );
         }
+        if let Some(wrap) = self.wrap {
+            dict.insert("wrap".to_string(), Value::serialize(wrap)?);
+        }
+        if let Some(line_ending) = self.line_ending {
+            dict.insert("line_ending".to_string(), Value::serialize(line_ending)?);
+        }
 
         let mut spacing = BTreeMap::new();
         if let Some(alphabets) = self.spacing_alphabets {
@@ -74,10 +194,90 @@ impl Provider for CliArgs {
         if let Some(digits) = self.spacing_digits {
             spacing.insert("digits".to_string(), Value::serialize(digits)?);
         }
+        if let Some(punctuation_as_fullwidth) = self.spacing_punctuation_as_fullwidth {
+            spacing.insert(
+                "punctuation_as_fullwidth".to_string(),
+                Value::from(punctuation_as_fullwidth),
+            );
+        }
+        if let Some(inline_code) = self.spacing_inline_code {
+            spacing.insert("inline_code".to_string(), Value::serialize(inline_code)?);
+        }
+        if let Some(markup_boundaries) = self.spacing_markup_boundaries {
+            spacing.insert(
+                "markup_boundaries".to_string(),
+                Value::from(markup_boundaries),
+            );
+        }
         if !spacing.is_empty() {
             dict.insert("spacing".to_string(), Value::from(spacing));
         }
 
+        let mut normalize = BTreeMap::new();
+        if let Some(rule) = self.normalize_fullwidth_alphanumerics {
+            normalize.insert(
+                "fullwidth_alphanumerics".to_string(),
+                Value::serialize(rule)?,
+            );
+        }
+        if let Some(rule) = self.normalize_halfwidth_katakana {
+            normalize.insert("halfwidth_katakana".to_string(), Value::serialize(rule)?);
+        }
+        if let Some(rule) = self.normalize_fullwidth_space {
+            normalize.insert("fullwidth_space".to_string(), Value::serialize(rule)?);
+        }
+        if !normalize.is_empty() {
+            dict.insert("normalize".to_string(), Value::from(normalize));
+        }
+
+        let mut punctuation = BTreeMap::new();
+        if let Some(period) = self.punctuation_period {
+            punctuation.insert("period".to_string(), Value::serialize(period)?);
+        }
+        if let Some(comma) = self.punctuation_comma {
+            punctuation.insert("comma".to_string(), Value::serialize(comma)?);
+        }
+        if let Some(parentheses) = self.punctuation_parentheses {
+            punctuation.insert("parentheses".to_string(), Value::serialize(parentheses)?);
+        }
+        if !punctuation.is_empty() {
+            dict.insert("punctuation".to_string(), Value::from(punctuation));
+        }
+
+        let mut line_break = BTreeMap::new();
+        if let Some(preset) = self.line_break_preset {
+            line_break.insert("preset".to_string(), Value::serialize(preset)?);
+        }
+        if let Some(characters) = &self.line_break_prohibited_start {
+            let set = CharacterSet::Replace(characters.clone());
+            line_break.insert("prohibited_start".to_string(), Value::serialize(set)?);
+        }
+        if let Some(characters) = &self.line_break_prohibited_end {
+            let set = CharacterSet::Replace(characters.clone());
+            line_break.insert("prohibited_end".to_string(), Value::serialize(set)?);
+        }
+        if let Some(hanging_punctuation) = self.line_break_hanging_punctuation {
+            line_break.insert(
+                "hanging_punctuation".to_string(),
+                Value::from(hanging_punctuation),
+            );
+        }
+        if let Some(strategy) = self.line_break_strategy {
+            line_break.insert("strategy".to_string(), Value::serialize(strategy)?);
+        }
+        if let Some(tolerance) = self.line_break_push_in_tolerance {
+            line_break.insert("push_in_tolerance".to_string(), Value::from(tolerance));
+        }
+        if !self.line_break_inseparable.is_empty() {
+            line_break.insert(
+                "inseparable".to_string(),
+                Value::serialize(&self.line_break_inseparable)?,
+            );
+        }
+        if !line_break.is_empty() {
+            dict.insert("line_break".to_string(), Value::from(line_break));
+        }
+
         let mut map = BTreeMap::new();
         map.insert(Profile::Default, dict);
 
@@ -90,29 +290,127 @@ pub enum Commands {
     /// Format files according to CJK text formatting rules.
     Format {
         /// Replace each input file with its formatted content instead of writing to stdout.
-        #[arg(short, long, requires = "filenames")]
+        #[arg(short, long, requires = "filenames", conflicts_with_all = ["check", "diff"])]
         write: bool,
 
-        /// File(s) to process.
+        /// List the files that would be changed without writing anything.
+        ///
+        /// Exits with status 1 if any file would be changed.
+        #[arg(long)]
+        check: bool,
+
+        /// Print a unified diff between each file and its formatted content.
+        #[arg(long)]
+        diff: bool,
+
+        /// Format only the paragraphs touching a range, such as `3:1-5:10`.
+        ///
+        /// Lines and columns start at 1, and the end column is exclusive.
+        #[arg(long, value_name = "LINE:COL-LINE:COL", value_parser = parse_range)]
+        range: Option<(Position, Position)>,
+
+        /// Format only the paragraphs touching a range of lines, such as `10-20`.
+        #[arg(
+            long,
+            value_name = "FIRST-LAST",
+            value_parser = parse_lines,
+            conflicts_with = "range"
+        )]
+        lines: Option<(Position, Position)>,
+
+        /// Format only the paragraphs changed since a git revision, such as `HEAD` or `main`.
+        #[arg(
+            long,
+            value_name = "REV",
+            requires = "filenames",
+            conflicts_with_all = ["range", "lines"]
+        )]
+        since: Option<String>,
+
+        /// Files or directories to process. Directories are searched recursively.
         #[arg()]
         filenames: Vec<PathBuf>,
     },
 
     /// Check whether formatting is correct without modifying the files.
+    ///
+    /// Exits with status 0 if no problems were found, 1 if any diagnostics were
+    /// reported, or 2 if the command could not run because of a usage, I/O, or
+    /// parse error.
     Check {
-        /// File(s) to process.
+        /// Print a one-line summary of the checked files and found diagnostics.
+        ///
+        /// The summary is written to stderr unless the output format is `text`.
+        #[arg(long)]
+        summary: bool,
+
+        /// Format of the reported diagnostics.
+        #[arg(value_enum, long, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+
+        /// Report only diagnostics in the paragraphs changed since a git revision, such as `HEAD`.
+        #[arg(long, value_name = "REV", requires = "filenames")]
+        since: Option<String>,
+
+        /// Files or directories to process. Directories are searched recursively.
         #[arg()]
         filenames: Vec<PathBuf>,
     },
 
+    /// Run a language server over stdio for use from editors.
+    ///
+    /// The server publishes diagnostics of the `check` command while documents
+    /// are edited, formats documents or ranges of them, and offers quick fixes
+    /// for spacing problems.
+    Lsp,
+
     /// Print the parsed concrete syntax tree for debugging.
     DebugCst {
-        /// File(s) to process.
+        /// Files or directories to process. Directories are searched recursively.
         #[arg()]
         filenames: Vec<PathBuf>,
     },
 }
 
+/// Parses a `LINE:COL-LINE:COL` range of one-based positions.
+fn parse_range(value: &str) -> Result<(Position, Position), String> {
+    let (start, end) = value
+        .split_once('-')
+        .ok_or_else(|| "expected LINE:COL-LINE:COL".to_string())?;
+    let parse_position = |position: &str| {
+        let (line, column) = position
+            .split_once(':')
+            .ok_or_else(|| "expected LINE:COL-LINE:COL".to_string())?;
+        Ok::<_, String>(Position::new(
+            parse_one_based(line)?,
+            parse_one_based(column)?,
+        ))
+    };
+    let (start, end) = (parse_position(start)?, parse_position(end)?);
+    if (end.line, end.column) < (start.line, start.column) {
+        return Err("the end of the range precedes its start".to_string());
+    }
+    Ok((start, end))
+}
+
+/// Parses a `FIRST-LAST` range of one-based line numbers, or a single line.
+fn parse_lines(value: &str) -> Result<(Position, Position), String> {
+    let (first, last) = value.split_once('-').unwrap_or((value, value));
+    let (first, last) = (parse_one_based(first)?, parse_one_based(last)?);
+    if last < first {
+        return Err("the last line precedes the first line".to_string());
+    }
+    Ok((Position::new(first, 0), Position::new(last + 1, 0)))
+}
+
+/// Parses a one-based number into a zero-based index.
+fn parse_one_based(value: &str) -> Result<u32, String> {
+    match value.trim().parse::<u32>() {
+        Ok(number) if 0 < number => Ok(number - 1),
+        _ => Err(format!("`{value}` is not a number starting at 1")),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use figment::{Figment, providers::Serialized};
@@ -140,7 +438,9 @@ mod tests {
             .expect("the write command-line arguments should parse");
 
         match args.command {
-            Commands::Format { write, filenames } => {
+            Commands::Format {
+                write, filenames, ..
+            } => {
                 assert!(write);
                 assert_eq!(filenames, [PathBuf::from("file.md")]);
             }
@@ -155,6 +455,109 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[rstest]
+    #[case("--check")]
+    #[case("--diff")]
+    fn format_write_flag_conflicts_with_check_and_diff(#[case] flag: &str) {
+        let result = CliArgs::try_parse_from(["cjkfmt", "format", "--write", flag, "file.md"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn format_check_and_diff_flags_can_be_combined() {
+        let args = CliArgs::try_parse_from(["cjkfmt", "format", "--check", "--diff", "file.md"])
+            .expect("the check and diff command-line arguments should parse");
+
+        match args.command {
+            Commands::Format { check, diff, .. } => {
+                assert!(check);
+                assert!(diff);
+            }
+            _ => panic!("expected format command"),
+        }
+    }
+
+    #[rstest]
+    #[case(&["--range", "3:1-5:10"], (Position::new(2, 0), Position::new(4, 9)))]
+    #[case(&["--range", "2:4-2:4"], (Position::new(1, 3), Position::new(1, 3)))]
+    #[case(&["--lines", "10-20"], (Position::new(9, 0), Position::new(20, 0)))]
+    #[case(&["--lines", "7"], (Position::new(6, 0), Position::new(7, 0)))]
+    fn format_range_flags_accept_one_based_ranges(
+        #[case] flags: &[&str],
+        #[case] expected: (Position, Position),
+    ) {
+        let arguments = ["cjkfmt", "format"].iter().chain(flags).chain(&["file.md"]);
+        let args = CliArgs::try_parse_from(arguments)
+            .expect("the range command-line arguments should parse");
+
+        match args.command {
+            Commands::Format { range, lines, .. } => {
+                assert_eq!(range.or(lines), Some(expected));
+            }
+            _ => panic!("expected format command"),
+        }
+    }
+
+    #[rstest]
+    #[case(&["--range", "3:1"])]
+    #[case(&["--range", "3-5"])]
+    #[case(&["--range", "0:1-5:1"])]
+    #[case(&["--range", "5:1-3:1"])]
+    #[case(&["--lines", "20-10"])]
+    #[case(&["--lines", "a-b"])]
+    #[case(&["--range", "3:1-5:1", "--lines", "3-5"])]
+    fn format_range_flags_reject_invalid_ranges(#[case] flags: &[&str]) {
+        let arguments = ["cjkfmt", "format"].iter().chain(flags).chain(&["file.md"]);
+        let result = CliArgs::try_parse_from(arguments);
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case("format")]
+    #[case("check")]
+    fn since_flag_accepts_a_revision(#[case] command: &str) {
+        let args = CliArgs::try_parse_from(["cjkfmt", command, "--since", "HEAD~1", "file.md"])
+            .expect("the since command-line arguments should parse");
+
+        match args.command {
+            Commands::Format { since, .. } | Commands::Check { since, .. } => {
+                assert_eq!(since.as_deref(), Some("HEAD~1"));
+            }
+            _ => panic!("expected format or check command"),
+        }
+    }
+
+    #[rstest]
+    #[case(&["format", "--since", "HEAD"])]
+    #[case(&["check", "--since", "HEAD"])]
+    #[case(&["format", "--since", "HEAD", "--lines", "1-2", "file.md"])]
+    fn since_flag_rejects_invalid_combinations(#[case] arguments: &[&str]) {
+        let result = CliArgs::try_parse_from(["cjkfmt"].iter().chain(arguments));
+
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case("text", OutputFormat::Text)]
+    #[case("json", OutputFormat::Json)]
+    #[case("jsonl", OutputFormat::Jsonl)]
+    #[case("sarif", OutputFormat::Sarif)]
+    #[case("github", OutputFormat::Github)]
+    fn check_output_format_flag_accepts_each_value(
+        #[case] value: &str,
+        #[case] expected: OutputFormat,
+    ) {
+        let args = CliArgs::try_parse_from(["cjkfmt", "check", "--output-format", value])
+            .expect("the output format command-line arguments should parse");
+
+        match args.command {
+            Commands::Check { output_format, .. } => assert_eq!(output_format, expected),
+            _ => panic!("expected check command"),
+        }
+    }
+
     #[test]
     fn max_width_flag_maps_clap_value_to_config() {
         let config = config_from(["cjkfmt", "--max-width", "42", "format"]);
@@ -174,6 +577,32 @@ mod tests {
         assert_eq!(config.ambiguous_width, expected);
     }
 
+    #[rstest]
+    #[case("split", WrapMode::Split)]
+    #[case("reflow", WrapMode::Reflow)]
+    fn wrap_flag_maps_each_clap_value_to_config(
+        #[case] value: &'static str,
+        #[case] expected: WrapMode,
+    ) {
+        let config = config_from(["cjkfmt", "--wrap", value, "format"]);
+
+        assert_eq!(config.wrap, expected);
+    }
+
+    #[rstest]
+    #[case("auto", LineEnding::Auto)]
+    #[case("lf", LineEnding::Lf)]
+    #[case("crlf", LineEnding::Crlf)]
+    #[case("native", LineEnding::Native)]
+    fn line_ending_flag_maps_each_clap_value_to_config(
+        #[case] value: &'static str,
+        #[case] expected: LineEnding,
+    ) {
+        let config = config_from(["cjkfmt", "--line-ending", value, "format"]);
+
+        assert_eq!(config.line_ending, expected);
+    }
+
     #[rstest]
     #[case("require", SpacingRule::Require)]
     #[case("prohibit", SpacingRule::Prohibit)]
@@ -200,6 +629,19 @@ mod tests {
         assert_eq!(config.spacing.digits, expected);
     }
 
+    #[rstest]
+    #[case("require", SpacingRule::Require)]
+    #[case("prohibit", SpacingRule::Prohibit)]
+    #[case("ignore", SpacingRule::Ignore)]
+    fn spacing_inline_code_flag_maps_each_clap_value_to_config(
+        #[case] value: &'static str,
+        #[case] expected: SpacingRule,
+    ) {
+        let config = config_from(["cjkfmt", "--spacing-inline-code", value, "format"]);
+
+        assert_eq!(config.spacing.inline_code, expected);
+    }
+
     #[test]
     fn spacing_flags_are_merged_as_independent_nested_config_values() {
         let config = config_from([
@@ -214,4 +656,159 @@ mod tests {
         assert_eq!(config.spacing.alphabets, SpacingRule::Require);
         assert_eq!(config.spacing.digits, SpacingRule::Prohibit);
     }
+
+    #[rstest]
+    #[case("standard", KinsokuPreset::Standard)]
+    #[case("strict", KinsokuPreset::Strict)]
+    #[case("loose", KinsokuPreset::Loose)]
+    fn line_break_preset_flag_maps_each_clap_value_to_config(
+        #[case] value: &'static str,
+        #[case] expected: KinsokuPreset,
+    ) {
+        let config = config_from(["cjkfmt", "--line-break-preset", value, "format"]);
+
+        assert_eq!(config.line_break.preset, expected);
+    }
+
+    #[test]
+    fn line_break_prohibited_flags_replace_the_sets() {
+        let config = config_from([
+            "cjkfmt",
+            "--line-break-prohibited-start",
+            "」。",
+            "--line-break-prohibited-end",
+            "「",
+            "format",
+        ]);
+
+        assert_eq!(config.line_break.prohibited_start(), "」。");
+        assert_eq!(config.line_break.prohibited_end(), "「");
+    }
+
+    #[test]
+    fn normalize_flags_map_each_clap_value_to_config() {
+        let config = config_from([
+            "cjkfmt",
+            "--normalize-fullwidth-alphanumerics",
+            "to_halfwidth",
+            "--normalize-halfwidth-katakana",
+            "to_fullwidth",
+            "--normalize-fullwidth-space",
+            "to_ascii",
+            "format",
+        ]);
+
+        assert_eq!(
+            config.normalize.fullwidth_alphanumerics,
+            FullwidthAlphanumerics::ToHalfwidth
+        );
+        assert_eq!(
+            config.normalize.halfwidth_katakana,
+            HalfwidthKatakana::ToFullwidth
+        );
+        assert_eq!(config.normalize.fullwidth_space, FullwidthSpace::ToAscii);
+
+        let config = config_from(["cjkfmt", "--normalize-fullwidth-space", "keep", "format"]);
+        assert_eq!(config.normalize.fullwidth_space, FullwidthSpace::Keep);
+    }
+
+    #[test]
+    fn punctuation_flags_map_each_clap_value_to_config() {
+        let config = config_from([
+            "cjkfmt",
+            "--punctuation-period",
+            "ideographic",
+            "--punctuation-comma",
+            "fullwidth",
+            "--punctuation-parentheses",
+            "fullwidth_around_cjk",
+            "format",
+        ]);
+
+        assert_eq!(config.punctuation.period, Period::Ideographic);
+        assert_eq!(config.punctuation.comma, Comma::Fullwidth);
+        assert_eq!(
+            config.punctuation.parentheses,
+            Parentheses::FullwidthAroundCjk
+        );
+
+        let config = config_from(["cjkfmt", "--punctuation-period", "keep", "format"]);
+        assert_eq!(config.punctuation.period, Period::Keep);
+    }
+
+    #[rstest]
+    #[case(&["cjkfmt", "format"], false)]
+    #[case(&["cjkfmt", "--spacing-punctuation-as-fullwidth", "format"], true)]
+    #[case(&["cjkfmt", "--spacing-punctuation-as-fullwidth=true", "format"], true)]
+    #[case(&["cjkfmt", "--spacing-punctuation-as-fullwidth=false", "format"], false)]
+    fn spacing_punctuation_as_fullwidth_flag_maps_to_config(
+        #[case] arguments: &[&'static str],
+        #[case] expected: bool,
+    ) {
+        let config = config_from(arguments.iter().copied());
+
+        assert_eq!(config.spacing.punctuation_as_fullwidth, expected);
+    }
+
+    #[rstest]
+    #[case(&["cjkfmt", "format"], false)]
+    #[case(&["cjkfmt", "--spacing-markup-boundaries", "format"], true)]
+    #[case(&["cjkfmt", "--spacing-markup-boundaries=true", "format"], true)]
+    #[case(&["cjkfmt", "--spacing-markup-boundaries=false", "format"], false)]
+    fn spacing_markup_boundaries_flag_maps_to_config(
+        #[case] arguments: &[&'static str],
+        #[case] expected: bool,
+    ) {
+        let config = config_from(arguments.iter().copied());
+
+        assert_eq!(config.spacing.markup_boundaries, expected);
+    }
+
+    #[rstest]
+    #[case(&["cjkfmt", "format"], false)]
+    #[case(&["cjkfmt", "--line-break-hanging-punctuation", "format"], true)]
+    #[case(&["cjkfmt", "--line-break-hanging-punctuation=true", "format"], true)]
+    #[case(&["cjkfmt", "--line-break-hanging-punctuation=false", "format"], false)]
+    fn line_break_hanging_punctuation_flag_maps_to_config(
+        #[case] arguments: &[&'static str],
+        #[case] expected: bool,
+    ) {
+        let config = config_from(arguments.iter().copied());
+
+        assert_eq!(config.line_break.hanging_punctuation, expected);
+    }
+
+    #[rstest]
+    #[case("push_out", KinsokuStrategy::PushOut)]
+    #[case("push_in", KinsokuStrategy::PushIn)]
+    #[case("min_raggedness", KinsokuStrategy::MinRaggedness)]
+    fn line_break_strategy_flag_maps_each_clap_value_to_config(
+        #[case] value: &'static str,
+        #[case] expected: KinsokuStrategy,
+    ) {
+        let config = config_from(["cjkfmt", "--line-break-strategy", value, "format"]);
+
+        assert_eq!(config.line_break.strategy, expected);
+    }
+
+    #[test]
+    fn line_break_push_in_tolerance_flag_overrides_config() {
+        let config = config_from(["cjkfmt", "--line-break-push-in-tolerance", "4", "format"]);
+
+        assert_eq!(config.line_break.push_in_tolerance, 4);
+    }
+
+    #[test]
+    fn line_break_inseparable_flag_can_be_repeated() {
+        let config = config_from([
+            "cjkfmt",
+            "--line-break-inseparable",
+            "C++",
+            "--line-break-inseparable",
+            "株式会社",
+            "format",
+        ]);
+
+        assert_eq!(config.line_break.inseparable, ["C++", "株式会社"]);
+    }
 }
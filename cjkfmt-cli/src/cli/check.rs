@@ -1,5 +1,6 @@
 use std::{
-    fs,
+    collections::BTreeMap,
+    fmt, fs,
     io::{Read, stdin},
     path::Path,
 };
@@ -7,15 +8,77 @@ use std::{
 use cjkfmt_parser::{Grammar, grammar_from_path};
 
 use crate::{
-    check::check_one_file, cli::utils::format_diagnostic, config::Config, document::Document,
+    check::check_one_file,
+    cli::{
+        args::OutputFormat, git::changed_lines, output::write_diagnostics, utils::collect_files,
+    },
+    config::Config,
+    document::Document,
+    format::paragraph_lines,
 };
 
-pub fn check_command<W, P>(stdout: &mut W, config: &Config, filenames: &[P]) -> anyhow::Result<()>
+/// How the `check` command reports its results.
+#[derive(Debug, Default, Clone)]
+pub struct CheckOptions {
+    /// Print a one-line summary after the diagnostics.
+    pub summary: bool,
+
+    /// Format of the reported diagnostics.
+    pub output_format: OutputFormat,
+
+    /// Report only the diagnostics in the paragraphs changed since the git
+    /// revision.
+    pub since: Option<String>,
+}
+
+/// Statistics of a `check` run.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CheckSummary {
+    /// The number of checked files, counting standard input as one.
+    pub num_files: usize,
+
+    /// The number of diagnostics found, by diagnostic code.
+    pub num_diagnostics_by_code: BTreeMap<String, usize>,
+}
+
+impl CheckSummary {
+    /// Returns the total number of diagnostics found.
+    pub fn num_diagnostics(&self) -> usize {
+        self.num_diagnostics_by_code.values().sum()
+    }
+}
+
+impl fmt::Display for CheckSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let files = if self.num_files == 1 { "file" } else { "files" };
+        write!(f, "Checked {} {files}: ", self.num_files)?;
+        match self.num_diagnostics() {
+            0 => write!(f, "no diagnostics"),
+            n => {
+                let diagnostics = if n == 1 { "diagnostic" } else { "diagnostics" };
+                let by_code: Vec<String> = self
+                    .num_diagnostics_by_code
+                    .iter()
+                    .map(|(code, count)| format!("{code}: {count}"))
+                    .collect();
+                write!(f, "{n} {diagnostics} ({})", by_code.join(", "))
+            }
+        }
+    }
+}
+
+pub fn check_command<W, P>(
+    stdout: &mut W,
+    config: &Config,
+    filenames: &[P],
+    options: CheckOptions,
+) -> anyhow::Result<CheckSummary>
 where
     W: std::io::Write,
     P: AsRef<Path>,
 {
     let mut diagnostics = Vec::new();
+    let mut check_summary = CheckSummary::default();
 
     // Read content of the specified files or standard input
     if filenames.is_empty() {
@@ -25,9 +88,10 @@ where
         document.parse()?;
         let diagnostic = check_one_file(config, &document)?;
         diagnostics.extend(diagnostic);
+        check_summary.num_files += 1;
     } else {
-        for filename in filenames {
-            let filename = filename.as_ref();
+        for filename in collect_files(filenames)? {
+            let filename = filename.as_path();
             let grammar = grammar_from_path(filename);
             let content = fs::read_to_string(filename)?;
             let mut document = Document::new(
@@ -36,14 +100,38 @@ where
                 Some(filename.to_string_lossy().to_string()),
             );
             document.parse()?;
-            let diagnostics_ = check_one_file(config, &document)?;
+            let mut diagnostics_ = check_one_file(config, &document)?;
+            if let Some(revision) = &options.since {
+                let paragraphs: Vec<_> = changed_lines(filename, revision)?
+                    .into_iter()
+                    .map(|lines| paragraph_lines(&document.content, lines))
+                    .collect();
+                diagnostics_.retain(|diagnostic| {
+                    let line = diagnostic.start.line as usize;
+                    paragraphs.iter().any(|lines| lines.contains(&line))
+                });
+            }
             diagnostics.extend(diagnostics_);
+            check_summary.num_files += 1;
         }
     }
+    write_diagnostics(stdout, options.output_format, &diagnostics)?;
     for diagnostic in diagnostics {
-        writeln!(stdout, "{}", format_diagnostic(&diagnostic))?;
+        *check_summary
+            .num_diagnostics_by_code
+            .entry(diagnostic.code)
+            .or_default() += 1;
     }
-    Ok(())
+
+    // Keep machine-readable output parsable by sending the summary to stderr
+    if options.summary {
+        if options.output_format == OutputFormat::Text {
+            writeln!(stdout, "{check_summary}")?;
+        } else {
+            eprintln!("{check_summary}");
+        }
+    }
+    Ok(check_summary)
 }
 
 #[cfg(test)]
@@ -51,7 +139,14 @@ mod tests {
     use tempfile::tempdir;
 
     use super::*;
-    use crate::config::SpacingRule;
+    use crate::{cli::git::tests::run_git, config::SpacingRule};
+
+    fn summary() -> CheckOptions {
+        CheckOptions {
+            summary: true,
+            ..CheckOptions::default()
+        }
+    }
 
     #[test]
     fn check_command_keeps_markdown_fallback_for_uppercase_json_files() {
@@ -66,11 +161,129 @@ mod tests {
         config.spacing.alphabets = SpacingRule::Require;
 
         let mut output = Vec::new();
-        check_command(&mut output, &config, &[&path]).unwrap();
+        check_command(&mut output, &config, &[&path], CheckOptions::default()).unwrap();
 
         assert!(
             String::from_utf8(output).unwrap().contains("W002"),
             "uppercase .JSON should retain the Markdown grammar fallback"
         );
     }
+
+    #[test]
+    fn check_command_counts_files_and_diagnostics_by_code() {
+        let directory = tempdir().unwrap();
+        let clean = directory.path().join("clean.md");
+        let dirty = directory.path().join("dirty.md");
+        fs::write(&clean, "漢 A\n").unwrap();
+        fs::write(&dirty, "漢A\n漢字漢字漢字\n").unwrap();
+
+        let mut config = Config {
+            max_width: 10,
+            ..Config::default()
+        };
+        config.spacing.alphabets = SpacingRule::Require;
+
+        let mut output = Vec::new();
+        let summary = check_command(&mut output, &config, &[&clean, &dirty], summary()).unwrap();
+
+        assert_eq!(summary.num_files, 2);
+        assert_eq!(summary.num_diagnostics(), 2);
+        assert_eq!(
+            String::from_utf8(output).unwrap().lines().last(),
+            Some("Checked 2 files: 2 diagnostics (W001: 1, W002: 1)")
+        );
+    }
+
+    #[test]
+    fn check_command_reports_no_diagnostics_for_clean_files() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("clean.md");
+        fs::write(&path, "漢字\n").unwrap();
+
+        let mut output = Vec::new();
+        let summary = check_command(&mut output, &Config::default(), &[&path], summary()).unwrap();
+
+        assert_eq!(summary.num_diagnostics(), 0);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Checked 1 file: no diagnostics\n"
+        );
+    }
+
+    #[test]
+    fn check_command_omits_summary_unless_requested() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("clean.md");
+        fs::write(&path, "漢字\n").unwrap();
+
+        let mut output = Vec::new();
+        check_command(
+            &mut output,
+            &Config::default(),
+            &[&path],
+            CheckOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn check_command_writes_diagnostics_in_the_requested_format() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("dirty.md");
+        fs::write(&path, "漢A\n").unwrap();
+
+        let mut config = Config::default();
+        config.spacing.alphabets = SpacingRule::Require;
+        let options = CheckOptions {
+            summary: true,
+            output_format: OutputFormat::Jsonl,
+            ..CheckOptions::default()
+        };
+
+        let mut output = Vec::new();
+        check_command(&mut output, &config, &[&path], options).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines.len(),
+            1,
+            "the summary should not be mixed into stdout"
+        );
+        assert_eq!(lines[0]["code"], "W002");
+    }
+
+    #[test]
+    fn check_command_since_reports_only_changed_paragraphs() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("document.md");
+        run_git(directory.path(), &["init", "--quiet"]);
+        fs::write(&path, "漢A\n\n漢字\n漢字\n\n漢C\n").unwrap();
+        run_git(directory.path(), &["add", "document.md"]);
+        run_git(
+            directory.path(),
+            &["commit", "--quiet", "-m", "Add a document"],
+        );
+        fs::write(&path, "漢A\n\n漢B\n漢字\n\n漢C\n").unwrap();
+
+        let mut config = Config::default();
+        config.spacing.alphabets = SpacingRule::Require;
+        let options = CheckOptions {
+            output_format: OutputFormat::Jsonl,
+            since: Some("HEAD".to_string()),
+            ..CheckOptions::default()
+        };
+
+        let mut output = Vec::new();
+        let summary = check_command(&mut output, &config, &[&path], options).unwrap();
+
+        assert_eq!(summary.num_diagnostics(), 1);
+        let diagnostic: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(diagnostic["start"]["line"], 2);
+    }
 }
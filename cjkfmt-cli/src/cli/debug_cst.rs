@@ -7,6 +7,8 @@ use std::{
 use cjkfmt_parser::{Grammar, grammar_from_path, parse};
 use tree_sitter::{Node, Tree};
 
+use crate::cli::utils::collect_files;
+
 pub fn debug_cst_command<W, P>(stdout: &mut W, filenames: &[P]) -> anyhow::Result<()>
 where
     W: std::io::Write,
@@ -31,8 +33,8 @@ where
         stdin.read_to_string(&mut content)?;
         write_tree(stdout, Grammar::Markdown, &content)?;
     } else {
-        for filename in filenames {
-            let filename = filename.as_ref();
+        for filename in collect_files(filenames)? {
+            let filename = filename.as_path();
             let grammar = grammar_from_path(filename);
             let content = fs::read_to_string(filename)?;
             write_tree(stdout, grammar, &content)?;
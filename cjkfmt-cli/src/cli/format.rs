@@ -4,19 +4,48 @@ use std::{
     path::Path,
 };
 
-use crate::{config::Config, format::format_one_file};
+use cjkfmt_core::position::Position;
+use similar::TextDiff;
+
+use crate::{
+    cli::git::changed_lines,
+    cli::utils::collect_files,
+    config::Config,
+    format::{format_content_lines, format_content_range, format_one_file},
+    markdown_spacing::apply_text_edits,
+};
+
+/// How the `format` command reports the formatted content.
+#[derive(Debug, Default, Clone)]
+pub struct FormatOptions {
+    /// Replace each input file with its formatted content.
+    pub write: bool,
+
+    /// List the files whose content would change, without writing anything.
+    pub check: bool,
+
+    /// Print a unified diff between the original and the formatted content.
+    pub diff: bool,
+
+    /// Format only the paragraphs touching the range from the first to the
+    /// second position.
+    pub range: Option<(Position, Position)>,
+
+    /// Format only the paragraphs changed since the git revision.
+    pub since: Option<String>,
+}
 
 pub fn format_command<W: std::io::Write, P: AsRef<Path>>(
     stdout: &mut W,
     config: &Config,
     filenames: &[P],
-    write: bool,
-) -> anyhow::Result<()> {
+    options: &FormatOptions,
+) -> anyhow::Result<usize> {
     let mut stdin = stdin();
-    format_command_with_reader(stdout, config, filenames, write, &mut stdin)
+    format_command_with_reader(stdout, config, filenames, options, &mut stdin)
 }
 
-fn is_markdown_path(path: &Path) -> bool {
+pub(crate) fn is_markdown_path(path: &Path) -> bool {
     path.extension()
         .and_then(|extension| extension.to_str())
         .is_some_and(|extension| {
@@ -24,51 +53,128 @@ fn is_markdown_path(path: &Path) -> bool {
         })
 }
 
+/// Formats the files, or standard input if no file is given, and returns the
+/// number of inputs whose content changed by formatting.
 fn format_command_with_reader<W, P, R>(
     stdout: &mut W,
     config: &Config,
     filenames: &[P],
-    write: bool,
+    options: &FormatOptions,
     stdin: &mut R,
-) -> anyhow::Result<()>
+) -> anyhow::Result<usize>
 where
     W: std::io::Write,
     P: AsRef<Path>,
     R: Read,
 {
+    let mut num_changed = 0;
+
     // Read content of standard input only for normal stdout mode. The CLI
     // requires a filename when `--write` is set, so write mode never attempts
     // to consume stdin.
-    if filenames.is_empty() && !write {
+    if filenames.is_empty() && !options.write {
         let mut content = String::with_capacity(1024);
         stdin.read_to_string(&mut content)?;
-        format_one_file(stdout, config, false, &content)?;
+        let formatted = format_content(config, options, None, &content)?;
+        if report(stdout, options, "<stdin>", &content, &formatted)? {
+            num_changed += 1;
+        }
     } else {
-        for filename in filenames {
-            let filename = filename.as_ref();
-            let apply_markdown_spacing = is_markdown_path(filename);
+        for filename in collect_files(filenames)? {
+            let filename = filename.as_path();
             let content = fs::read_to_string(filename)?;
+            let formatted = format_content(config, options, Some(filename), &content)?;
 
-            if write {
-                let mut formatted = Vec::new();
-                format_one_file(&mut formatted, config, apply_markdown_spacing, &content)?;
-                fs::write(filename, formatted)?;
-            } else {
-                format_one_file(stdout, config, apply_markdown_spacing, &content)?;
+            // Files formatting does not change are not written, which keeps
+            // their modification times for build tools and file watchers.
+            if options.write {
+                if formatted != content.as_bytes() {
+                    fs::write(filename, &formatted)?;
+                    num_changed += 1;
+                }
+            } else if report(
+                stdout,
+                options,
+                &filename.to_string_lossy(),
+                &content,
+                &formatted,
+            )? {
+                num_changed += 1;
             }
         }
     }
-    Ok(())
+    Ok(num_changed)
+}
+
+/// Formats the content of a file, or of standard input if no path is given.
+///
+/// If the options have a range or a git revision, only the paragraphs in the
+/// range or changed since the revision are formatted.
+fn format_content(
+    config: &Config,
+    options: &FormatOptions,
+    path: Option<&Path>,
+    content: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let markdown = path.is_some_and(is_markdown_path);
+    let lines = match (options.range, &options.since, path) {
+        (Some((start, end)), _, _) => {
+            Some(format_content_range(config, markdown, content, start, end)?)
+        }
+        (None, Some(revision), Some(path)) => {
+            let lines = changed_lines(path, revision)?;
+            Some(format_content_lines(config, markdown, content, &lines)?)
+        }
+        _ => None,
+    };
+    if let Some(edits) = lines {
+        return Ok(apply_text_edits(content, edits)?.into_bytes());
+    }
+    let mut formatted = Vec::new();
+    format_one_file(&mut formatted, config, markdown, content)?;
+    Ok(formatted)
+}
+
+/// Reports the formatted content of an input according to the options and
+/// returns whether formatting changed the content.
+fn report<W: std::io::Write>(
+    stdout: &mut W,
+    options: &FormatOptions,
+    name: &str,
+    original: &str,
+    formatted: &[u8],
+) -> anyhow::Result<bool> {
+    let changed = formatted != original.as_bytes();
+    if !options.check && !options.diff {
+        stdout.write_all(formatted)?;
+        return Ok(changed);
+    }
+
+    if changed && options.diff {
+        let formatted = String::from_utf8_lossy(formatted);
+        let diff = TextDiff::from_lines(original, formatted.as_ref());
+        write!(stdout, "{}", diff.unified_diff().header(name, name))?;
+    } else if changed {
+        writeln!(stdout, "{name}")?;
+    }
+    Ok(changed)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{fs, path::PathBuf};
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    };
 
     use tempfile::tempdir;
 
     use super::*;
-    use crate::config::{Config, SpacingRule};
+    use crate::{
+        cli::git::tests::run_git,
+        config::{Config, SpacingRule},
+    };
 
     fn config() -> Config {
         let mut config = Config {
@@ -101,7 +207,7 @@ mod tests {
         }
 
         let mut output = Vec::new();
-        format_command(&mut output, &config(), &paths, false).unwrap();
+        format_command(&mut output, &config(), &paths, &FormatOptions::default()).unwrap();
 
         let expected: String = cases.iter().map(|(_, output)| *output).collect();
         assert_eq!(String::from_utf8(output).unwrap(), expected);
@@ -124,21 +230,175 @@ mod tests {
         fs::write(&text, "漢A\n").unwrap();
 
         let mut output = Vec::new();
-        format_command(&mut output, &config(), &[&markdown, &text], true).unwrap();
+        let options = FormatOptions {
+            write: true,
+            ..FormatOptions::default()
+        };
+        format_command(&mut output, &config(), &[&markdown, &text], &options).unwrap();
 
         assert!(output.is_empty());
         assert_eq!(fs::read_to_string(markdown).unwrap(), "漢 A\n");
         assert_eq!(fs::read_to_string(text).unwrap(), "漢A\n");
     }
 
+    #[test]
+    fn format_command_does_not_write_unchanged_files() {
+        let directory = tempdir().unwrap();
+        let text = directory.path().join("document.txt");
+        fs::write(&text, "漢A\n").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        fs::File::options()
+            .write(true)
+            .open(&text)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let options = FormatOptions {
+            write: true,
+            ..FormatOptions::default()
+        };
+        let num_changed = format_command(&mut Vec::new(), &config(), &[&text], &options).unwrap();
+
+        assert_eq!(num_changed, 0);
+        assert_eq!(fs::metadata(&text).unwrap().modified().unwrap(), modified);
+    }
+
     #[test]
     fn format_command_does_not_assume_stdin_is_markdown() {
         let mut input = "漢A\n".as_bytes();
         let mut output = Vec::new();
 
-        format_command_with_reader(&mut output, &config(), &[] as &[PathBuf], false, &mut input)
-            .unwrap();
+        format_command_with_reader(
+            &mut output,
+            &config(),
+            &[] as &[PathBuf],
+            &FormatOptions::default(),
+            &mut input,
+        )
+        .unwrap();
 
         assert_eq!(String::from_utf8(output).unwrap(), "漢A\n");
     }
+
+    #[test]
+    fn format_command_check_lists_changed_files_without_writing() {
+        let directory = tempdir().unwrap();
+        let changed = directory.path().join("changed.md");
+        let unchanged = directory.path().join("unchanged.md");
+        fs::write(&changed, "漢A\n").unwrap();
+        fs::write(&unchanged, "漢 A\n").unwrap();
+
+        let options = FormatOptions {
+            check: true,
+            ..FormatOptions::default()
+        };
+        let mut output = Vec::new();
+        let num_changed =
+            format_command(&mut output, &config(), &[&changed, &unchanged], &options).unwrap();
+
+        assert_eq!(num_changed, 1);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{}\n", changed.to_string_lossy())
+        );
+        assert_eq!(fs::read_to_string(changed).unwrap(), "漢A\n");
+    }
+
+    #[test]
+    fn format_command_diff_prints_unified_diff_of_changed_files() {
+        let directory = tempdir().unwrap();
+        let changed = directory.path().join("changed.md");
+        let unchanged = directory.path().join("unchanged.md");
+        fs::write(&changed, "漢字\n漢A\n").unwrap();
+        fs::write(&unchanged, "漢 A\n").unwrap();
+
+        let options = FormatOptions {
+            diff: true,
+            ..FormatOptions::default()
+        };
+        let mut output = Vec::new();
+        let num_changed =
+            format_command(&mut output, &config(), &[&changed, &unchanged], &options).unwrap();
+
+        let name = changed.to_string_lossy();
+        assert_eq!(num_changed, 1);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("--- {name}\n+++ {name}\n@@ -1,2 +1,2 @@\n 漢字\n-漢A\n+漢 A\n")
+        );
+        assert_eq!(fs::read_to_string(changed).unwrap(), "漢字\n漢A\n");
+    }
+
+    #[test]
+    fn format_command_writes_supported_files_found_in_directories() {
+        let directory = tempdir().unwrap();
+        let nested = directory.path().join("docs");
+        fs::create_dir(&nested).unwrap();
+        let markdown = nested.join("document.md");
+        let text = nested.join("document.txt");
+        fs::write(&markdown, "漢A\n").unwrap();
+        fs::write(&text, "漢A\n").unwrap();
+
+        let options = FormatOptions {
+            write: true,
+            ..FormatOptions::default()
+        };
+        let mut output = Vec::new();
+        let num_changed =
+            format_command(&mut output, &config(), &[directory.path()], &options).unwrap();
+
+        assert_eq!(num_changed, 1);
+        assert_eq!(fs::read_to_string(markdown).unwrap(), "漢 A\n");
+        assert_eq!(fs::read_to_string(text).unwrap(), "漢A\n");
+    }
+
+    #[test]
+    fn format_command_range_writes_only_the_touched_paragraphs() {
+        let directory = tempdir().unwrap();
+        let markdown = directory.path().join("document.md");
+        fs::write(&markdown, "漢A\n\n漢B\n漢C\n\n漢D\n").unwrap();
+
+        let options = FormatOptions {
+            write: true,
+            range: Some((Position::new(3, 0), Position::new(4, 0))),
+            ..FormatOptions::default()
+        };
+        let mut output = Vec::new();
+        let num_changed = format_command(&mut output, &config(), &[&markdown], &options).unwrap();
+
+        assert_eq!(num_changed, 1);
+        assert_eq!(
+            fs::read_to_string(markdown).unwrap(),
+            "漢A\n\n漢 B\n漢 C\n\n漢D\n"
+        );
+    }
+
+    #[test]
+    fn format_command_since_writes_only_changed_paragraphs() {
+        let directory = tempdir().unwrap();
+        let markdown = directory.path().join("document.md");
+        run_git(directory.path(), &["init", "--quiet"]);
+        fs::write(&markdown, "漢A\n\n漢字\n漢字\n\n漢D\n").unwrap();
+        run_git(directory.path(), &["add", "document.md"]);
+        run_git(
+            directory.path(),
+            &["commit", "--quiet", "-m", "Add a document"],
+        );
+        fs::write(&markdown, "漢A\n\n漢B\n漢C\n\n漢D\n").unwrap();
+
+        let options = FormatOptions {
+            write: true,
+            since: Some("HEAD".to_string()),
+            ..FormatOptions::default()
+        };
+        let mut output = Vec::new();
+        let num_changed = format_command(&mut output, &config(), &[&markdown], &options).unwrap();
+
+        assert_eq!(num_changed, 1);
+        assert_eq!(
+            fs::read_to_string(markdown).unwrap(),
+            "漢A\n\n漢 B\n漢 C\n\n漢D\n"
+        );
+    }
 }
@@ -0,0 +1,153 @@
+//! Lines of files changed relative to a git revision.
+use std::{ops::Range, path::Path, process::Command};
+
+use anyhow::Context;
+
+/// Returns the ranges of line indices of a file changed since a git revision.
+///
+/// The changes are taken from `git diff` between the revision and the working
+/// tree. A range of a deletion covers the lines before and after it. Every
+/// line of a file not tracked by git is reported as changed, unless git
+/// ignores the file.
+pub(crate) fn changed_lines(path: &Path, revision: &str) -> anyhow::Result<Vec<Range<usize>>> {
+    let output = git(
+        path,
+        &[
+            "diff",
+            "--no-color",
+            "--no-ext-diff",
+            "--unified=0",
+            revision,
+        ],
+    )?;
+    let ranges: Vec<Range<usize>> = output.lines().filter_map(hunk_lines).collect();
+    if ranges.is_empty() && !git(path, &["ls-files", "--others", "--exclude-standard"])?.is_empty()
+    {
+        return Ok(std::iter::once(0..usize::MAX).collect());
+    }
+    Ok(ranges)
+}
+
+/// Runs a git command on a file in the directory of the file and returns its
+/// standard output.
+fn git(path: &Path, arguments: &[&str]) -> anyhow::Result<String> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let filename = path
+        .file_name()
+        .with_context(|| format!("not a file: {}", path.display()))?;
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(arguments)
+        .arg("--")
+        .arg(filename)
+        .output()
+        .with_context(|| "failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            arguments[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Returns the range of line indices of the new side of a hunk header such as
+/// `@@ -1,2 +3,4 @@`, or `None` if the line is not a hunk header.
+fn hunk_lines(line: &str) -> Option<Range<usize>> {
+    let new_side = line.strip_prefix("@@ -")?.split(' ').nth(1)?;
+    let new_side = new_side.strip_prefix('+')?;
+    let (start, count) = match new_side.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (new_side.parse::<usize>().ok()?, 1),
+    };
+
+    // A deletion is reported as zero lines starting at the line before it.
+    if count == 0 {
+        Some(start.saturating_sub(1)..start + 1)
+    } else {
+        Some(start - 1..start - 1 + count)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::fs;
+
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    /// Runs a git command in a directory for setting up a test repository.
+    pub(crate) fn run_git(directory: &Path, arguments: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(directory)
+            .args([
+                "-c",
+                "user.name=cjkfmt",
+                "-c",
+                "user.email=cjkfmt@example.com",
+            ])
+            .args([
+                "-c",
+                "commit.gpgsign=false",
+                "-c",
+                "init.defaultBranch=main",
+            ])
+            .args(arguments)
+            .output()
+            .expect("failed to run git")
+            .status;
+        assert!(status.success(), "git {arguments:?} failed");
+    }
+
+    #[rstest]
+    #[case("@@ -3,2 +3,4 @@", Some(2..6))]
+    #[case("@@ -3 +3 @@ heading", Some(2..3))]
+    #[case("@@ -0,0 +1,3 @@", Some(0..3))]
+    #[case("@@ -4,2 +3,0 @@", Some(2..4))]
+    #[case("@@ -1,2 +0,0 @@", Some(0..1))]
+    #[case("+@@ -1 +1 @@", None)]
+    #[case("diff --git a/a.md b/a.md", None)]
+    fn hunk_lines_returns_the_new_lines(
+        #[case] line: &str,
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        assert_eq!(hunk_lines(line), expected);
+    }
+
+    #[test]
+    fn changed_lines_returns_the_lines_changed_since_a_revision() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("document.md");
+        run_git(directory.path(), &["init", "--quiet"]);
+        fs::write(&path, "a\nb\nc\nd\ne\n").unwrap();
+        run_git(directory.path(), &["add", "document.md"]);
+        run_git(
+            directory.path(),
+            &["commit", "--quiet", "-m", "Add a document"],
+        );
+
+        fs::write(&path, "a\nB\nc\ne\nf\n").unwrap();
+        assert_eq!(changed_lines(&path, "HEAD").unwrap(), [1..2, 2..4, 4..5]);
+
+        let untracked = directory.path().join("untracked.md");
+        fs::write(&untracked, "a\n").unwrap();
+        let lines = changed_lines(&untracked, "HEAD").unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0], 0..usize::MAX);
+
+        fs::write(directory.path().join(".gitignore"), "ignored.md\n").unwrap();
+        let ignored = directory.path().join("ignored.md");
+        fs::write(&ignored, "a\n").unwrap();
+        assert!(changed_lines(&ignored, "HEAD").unwrap().is_empty());
+
+        assert!(changed_lines(&path, "no-such-revision").is_err());
+    }
+}
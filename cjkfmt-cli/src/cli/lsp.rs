@@ -0,0 +1,596 @@
+//! A language server publishing diagnostics and formatting documents in editors.
+//!
+//! The server speaks the Language Server Protocol over stdio. Documents are
+//! synchronized in full on every change, and the configuration loaded at
+//! startup applies to every document. The grammar of a document is decided
+//! when it is opened, from its language identifier or else from its path.
+use std::collections::HashMap;
+
+use anyhow::Context;
+use cjkfmt_core::diagnostic::Diagnostic;
+use cjkfmt_parser::{Grammar, NodeVisitor, grammar_from_path};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOptions, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentFormattingParams,
+    DocumentRangeFormattingParams, InitializeResult, NumberOrString, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
+    notification::{self, Notification as _},
+    request::{self, Request as _},
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    check::check_one_file,
+    config::Config,
+    document::Document,
+    format::{format_one_file, format_range, line_edits},
+    spacing,
+    spacing_checker::SpacingChecker,
+    suppression::Suppressions,
+};
+
+/// Runs the language server over stdio until the client asks it to exit.
+pub fn lsp_command(config: &Config) -> anyhow::Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    run_server(&connection, config)?;
+
+    // The writer thread finishes only after the connection is dropped.
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn run_server(connection: &Connection, config: &Config) -> anyhow::Result<()> {
+    let (id, _params) = connection.initialize_start()?;
+    let result = InitializeResult {
+        capabilities: server_capabilities(),
+        server_info: Some(ServerInfo {
+            name: "cjkfmt".to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
+    };
+    connection.initialize_finish(id, serde_json::to_value(result)?)?;
+
+    let mut server = Server {
+        connection,
+        config,
+        documents: HashMap::new(),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                server.handle_request(request)?;
+            }
+            Message::Notification(notification) => {
+                // A broken document must not stop the server, so the error is
+                // logged to stderr which editors show as the server's log.
+                if let Err(error) = server.handle_notification(notification) {
+                    eprintln!("Error: {error:?}");
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn server_capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+            code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+            ..CodeActionOptions::default()
+        })),
+        ..ServerCapabilities::default()
+    }
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    config: &'a Config,
+
+    /// The documents opened in the client, not parsed.
+    documents: HashMap<Uri, Document>,
+}
+
+impl Server<'_> {
+    fn handle_request(&mut self, request: Request) -> anyhow::Result<()> {
+        let result = match request.method.as_str() {
+            request::Formatting::METHOD => params::<DocumentFormattingParams>(request.params)
+                .and_then(|params| self.formatting(&params.text_document.uri, None))
+                .and_then(to_value),
+            request::RangeFormatting::METHOD => {
+                params::<DocumentRangeFormattingParams>(request.params)
+                    .and_then(|params| {
+                        self.formatting(&params.text_document.uri, Some(params.range))
+                    })
+                    .and_then(to_value)
+            }
+            request::CodeActionRequest::METHOD => params::<CodeActionParams>(request.params)
+                .and_then(|params| self.code_actions(&params.text_document.uri, params.range))
+                .and_then(to_value),
+            method => {
+                let response = Response::new_err(
+                    request.id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request: {method}"),
+                );
+                self.connection.sender.send(response.into())?;
+                return Ok(());
+            }
+        };
+
+        let response = match result {
+            Ok(value) => Response::new_ok(request.id, value),
+            Err(error) => Response::new_err(
+                request.id,
+                ErrorCode::RequestFailed as i32,
+                format!("{error:#}"),
+            ),
+        };
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> anyhow::Result<()> {
+        match notification.method.as_str() {
+            notification::DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = params(notification.params)?;
+                let document = params.text_document;
+                let grammar = grammar_from_language_id(&document.language_id)
+                    .unwrap_or_else(|| grammar_from_path(document.uri.path().as_str()));
+                self.documents.insert(
+                    document.uri.clone(),
+                    Document::new(document.text, grammar, None::<String>),
+                );
+                self.publish_diagnostics(document.uri, Some(document.version))?;
+            }
+            notification::DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams = params(notification.params)?;
+                let document = params.text_document;
+
+                // The full content is sent on every change, so the last one wins.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents
+                        .entry(document.uri.clone())
+                        .and_modify(|opened| opened.content.clone_from(&change.text))
+                        .or_insert_with(|| {
+                            let grammar = grammar_from_path(document.uri.path().as_str());
+                            Document::new(change.text, grammar, None::<String>)
+                        });
+                }
+                self.publish_diagnostics(document.uri, Some(document.version))?;
+            }
+            notification::DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = params(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+
+                // Clear the diagnostics of the closed document.
+                self.send_diagnostics(uri, Vec::new(), None)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn document(&self, uri: &Uri) -> anyhow::Result<&Document> {
+        self.documents
+            .get(uri)
+            .with_context(|| format!("document not opened: {}", uri.as_str()))
+    }
+
+    fn parse(&self, uri: &Uri) -> anyhow::Result<Document> {
+        let mut document = self.document(uri)?.clone();
+        document.parse()?;
+        Ok(document)
+    }
+
+    fn publish_diagnostics(&self, uri: Uri, version: Option<i32>) -> anyhow::Result<()> {
+        let document = self.parse(&uri)?;
+        let diagnostics = check_one_file(self.config, &document)?
+            .iter()
+            .map(lsp_diagnostic)
+            .collect();
+        self.send_diagnostics(uri, diagnostics, version)
+    }
+
+    fn send_diagnostics(
+        &self,
+        uri: Uri,
+        diagnostics: Vec<lsp_types::Diagnostic>,
+        version: Option<i32>,
+    ) -> anyhow::Result<()> {
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, version);
+        let notification =
+            Notification::new(notification::PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+
+    /// Formats the document and returns the edits replacing the changed lines.
+    ///
+    /// If a range is given, only the paragraphs touching it are formatted.
+    fn formatting(&self, uri: &Uri, range: Option<Range>) -> anyhow::Result<Vec<TextEdit>> {
+        let document = self.document(uri)?;
+        let edits = match range {
+            Some(range) => format_range(
+                self.config,
+                document,
+                cjkfmt_core::position::Position::new(range.start.line, range.start.character),
+                cjkfmt_core::position::Position::new(range.end.line, range.end.character),
+            )?,
+            None => {
+                let markdown = document.grammar == Grammar::Markdown;
+                let mut formatted = Vec::new();
+                format_one_file(&mut formatted, self.config, markdown, &document.content)?;
+                line_edits(&document.content, &String::from_utf8(formatted)?)
+            }
+        };
+        Ok(edits
+            .iter()
+            .map(|edit| lsp_text_edit(&document.content, edit))
+            .collect())
+    }
+
+    /// Returns quick fixes for the spacing diagnostics in the range.
+    fn code_actions(&self, uri: &Uri, range: Range) -> anyhow::Result<Vec<CodeActionOrCommand>> {
+        let document = self.parse(uri)?;
+        let Some(tree) = document.tree() else {
+            return Ok(Vec::new());
+        };
+        let suppressions = Suppressions::new(&document.content, tree)?;
+        let mut spacing_checker = SpacingChecker::new(self.config, &document);
+        spacing_checker.walk(tree);
+
+        let mut actions = Vec::new();
+        for (diagnostic, edit) in spacing_checker
+            .diagnostics()
+            .iter()
+            .zip(spacing_checker.edits())
+        {
+            if suppressions.is_suppressed(diagnostic.start.line, &diagnostic.code) {
+                continue;
+            }
+            let diagnostic = lsp_diagnostic(diagnostic);
+            if diagnostic.range.end < range.start || range.end < diagnostic.range.start {
+                continue;
+            }
+
+            let title = if edit.replacement.is_empty() {
+                "Remove spaces"
+            } else {
+                "Insert a space"
+            };
+            let text_edit = lsp_text_edit(&document.content, edit);
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.to_string(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![text_edit])])),
+                    ..WorkspaceEdit::default()
+                }),
+                is_preferred: Some(true),
+                ..CodeAction::default()
+            }));
+        }
+        Ok(actions)
+    }
+}
+
+fn params<P: DeserializeOwned>(params: Value) -> anyhow::Result<P> {
+    serde_json::from_value(params).with_context(|| "invalid parameters")
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> anyhow::Result<Value> {
+    Ok(serde_json::to_value(value)?)
+}
+
+/// Returns the grammar of a language identifier sent by the client, if it is
+/// supported.
+fn grammar_from_language_id(language_id: &str) -> Option<Grammar> {
+    match language_id {
+        "markdown" => Some(Grammar::Markdown),
+        "json" | "jsonc" => Some(Grammar::Json),
+        _ => None,
+    }
+}
+
+fn lsp_diagnostic(diagnostic: &Diagnostic) -> lsp_types::Diagnostic {
+    lsp_types::Diagnostic {
+        range: Range::new(
+            Position::new(diagnostic.start.line, diagnostic.start.column),
+            Position::new(diagnostic.end.line, diagnostic.end.column),
+        ),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String(diagnostic.code.clone())),
+        source: Some("cjkfmt".to_string()),
+        message: diagnostic.message.clone(),
+        ..lsp_types::Diagnostic::default()
+    }
+}
+
+/// Returns the LSP position of a byte offset in `text`.
+fn position_at(text: &str, offset: usize) -> Position {
+    let position = cjkfmt_core::position::Position::from_offset(text, offset);
+    Position::new(position.line, position.column)
+}
+
+/// Converts an edit of byte offsets in `text` into an LSP text edit.
+fn lsp_text_edit(text: &str, edit: &spacing::TextEdit) -> TextEdit {
+    TextEdit::new(
+        Range::new(
+            position_at(text, edit.range.start),
+            position_at(text, edit.range.end),
+        ),
+        edit.replacement.clone(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, str::FromStr, time::Duration};
+
+    use lsp_server::RequestId;
+    use lsp_types::{
+        CodeActionContext, FormattingOptions, InitializeParams, InitializedParams,
+        PartialResultParams, TextDocumentContentChangeEvent, TextDocumentIdentifier,
+        TextDocumentItem, VersionedTextDocumentIdentifier, WorkDoneProgressParams,
+    };
+
+    use super::*;
+    use crate::config::SpacingRule;
+
+    /// An in-process client talking to a server running on another thread.
+    struct Client {
+        connection: Connection,
+        next_id: i32,
+        notifications: VecDeque<Notification>,
+    }
+
+    impl Client {
+        fn recv(&self) -> Message {
+            self.connection
+                .receiver
+                .recv_timeout(Duration::from_secs(10))
+                .expect("the server should send a message")
+        }
+
+        fn request<R: request::Request>(&mut self, params: R::Params) -> R::Result {
+            self.next_id += 1;
+            let id = RequestId::from(self.next_id);
+            let request = Request::new(id.clone(), R::METHOD.to_string(), params);
+            self.connection.sender.send(request.into()).unwrap();
+            loop {
+                match self.recv() {
+                    Message::Response(response) if response.id == id => {
+                        assert!(response.error.is_none(), "{:?}", response.error);
+                        let result = response.result.unwrap_or(Value::Null);
+                        return serde_json::from_value(result).unwrap();
+                    }
+                    Message::Notification(notification) => {
+                        self.notifications.push_back(notification)
+                    }
+                    message => panic!("unexpected message: {message:?}"),
+                }
+            }
+        }
+
+        fn notify<N: notification::Notification>(&self, params: N::Params) {
+            let notification = Notification::new(N::METHOD.to_string(), params);
+            self.connection.sender.send(notification.into()).unwrap();
+        }
+
+        fn diagnostics(&mut self) -> Vec<lsp_types::Diagnostic> {
+            let notification = match self.notifications.pop_front() {
+                Some(notification) => notification,
+                None => match self.recv() {
+                    Message::Notification(notification) => notification,
+                    message => panic!("unexpected message: {message:?}"),
+                },
+            };
+            assert_eq!(
+                notification.method,
+                notification::PublishDiagnostics::METHOD
+            );
+            let params: PublishDiagnosticsParams =
+                serde_json::from_value(notification.params).unwrap();
+            params.diagnostics
+        }
+
+        fn open(&mut self, text: &str) {
+            self.open_as(uri(), "markdown", text);
+        }
+
+        fn open_as(&mut self, uri: Uri, language_id: &str, text: &str) {
+            self.notify::<notification::DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(uri, language_id.to_string(), 1, text.into()),
+            });
+        }
+    }
+
+    fn uri() -> Uri {
+        Uri::from_str("file:///project/README.md").unwrap()
+    }
+
+    fn document() -> TextDocumentIdentifier {
+        TextDocumentIdentifier::new(uri())
+    }
+
+    /// Runs a server with the configuration and lets `test` talk to it.
+    fn with_server(config: Config, test: impl FnOnce(&mut Client)) {
+        let (server, client) = Connection::memory();
+        std::thread::scope(|scope| {
+            let handle = scope.spawn(|| run_server(&server, &config));
+            let mut client = Client {
+                connection: client,
+                next_id: 0,
+                notifications: VecDeque::new(),
+            };
+
+            let result = client.request::<request::Initialize>(InitializeParams::default());
+            assert_eq!(result.server_info.unwrap().name, "cjkfmt");
+            client.notify::<notification::Initialized>(InitializedParams {});
+
+            test(&mut client);
+
+            client.request::<request::Shutdown>(());
+            client.notify::<notification::Exit>(());
+            handle.join().unwrap().unwrap();
+        });
+    }
+
+    #[test]
+    fn publishes_diagnostics_on_open_and_change() {
+        let mut config = Config::default();
+        config.spacing.digits = SpacingRule::Require;
+
+        with_server(config, |client| {
+            client.open("# 漢1\n");
+            let diagnostics = client.diagnostics();
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(
+                diagnostics[0].code,
+                Some(NumberOrString::String("W002".to_string()))
+            );
+            assert_eq!(
+                diagnostics[0].range,
+                Range::new(Position::new(0, 3), Position::new(0, 4))
+            );
+
+            client.notify::<notification::DidChangeTextDocument>(DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(uri(), 2),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text: "# 漢 1\n".to_string(),
+                }],
+            });
+            assert_eq!(client.diagnostics(), []);
+        });
+    }
+
+    #[test]
+    fn formats_the_whole_document() {
+        let config = Config {
+            max_width: 4,
+            ..Config::default()
+        };
+
+        with_server(config, |client| {
+            client.open("あいうえお\n");
+            client.diagnostics();
+
+            let edits = client.request::<request::Formatting>(DocumentFormattingParams {
+                text_document: document(),
+                options: FormattingOptions::default(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            });
+            assert_eq!(
+                edits,
+                Some(vec![TextEdit::new(
+                    Range::new(Position::new(0, 0), Position::new(1, 0)),
+                    "あい\nうえ\nお\n".to_string(),
+                )])
+            );
+        });
+    }
+
+    #[test]
+    fn formats_only_the_lines_in_the_range() {
+        let config = Config {
+            max_width: 4,
+            ..Config::default()
+        };
+
+        with_server(config, |client| {
+            client.open("あいうえお\n\nかきくけこ");
+            client.diagnostics();
+
+            let edits = client.request::<request::RangeFormatting>(DocumentRangeFormattingParams {
+                text_document: document(),
+                range: Range::new(Position::new(2, 1), Position::new(2, 3)),
+                options: FormattingOptions::default(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            });
+            assert_eq!(
+                edits,
+                Some(vec![TextEdit::new(
+                    Range::new(Position::new(2, 0), Position::new(2, 5)),
+                    "かき\nくけ\nこ".to_string(),
+                )])
+            );
+        });
+    }
+
+    #[test]
+    fn formats_with_the_grammar_of_the_language_id() {
+        let mut config = Config::default();
+        config.spacing.alphabets = SpacingRule::Require;
+
+        with_server(config, |client| {
+            let uri = Uri::from_str("file:///project/notes.txt").unwrap();
+            client.open_as(uri.clone(), "markdown", "漢A\n");
+            client.diagnostics();
+
+            let expected = Some(vec![TextEdit::new(
+                Range::new(Position::new(0, 0), Position::new(1, 0)),
+                "漢 A\n".to_string(),
+            )]);
+            let edits = client.request::<request::Formatting>(DocumentFormattingParams {
+                text_document: TextDocumentIdentifier::new(uri.clone()),
+                options: FormattingOptions::default(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            });
+            assert_eq!(edits, expected);
+            let edits = client.request::<request::RangeFormatting>(DocumentRangeFormattingParams {
+                text_document: TextDocumentIdentifier::new(uri),
+                range: Range::new(Position::new(0, 0), Position::new(0, 2)),
+                options: FormattingOptions::default(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            });
+            assert_eq!(edits, expected);
+        });
+    }
+
+    #[test]
+    fn offers_quick_fixes_for_spacing_diagnostics() {
+        let mut config = Config::default();
+        config.spacing.alphabets = SpacingRule::Require;
+
+        with_server(config, |client| {
+            client.open("漢A\n\n字B\n");
+            client.diagnostics();
+
+            let actions = client.request::<request::CodeActionRequest>(CodeActionParams {
+                text_document: document(),
+                range: Range::new(Position::new(2, 0), Position::new(2, 2)),
+                context: CodeActionContext::default(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            });
+            let Some([CodeActionOrCommand::CodeAction(action)]) = actions.as_deref() else {
+                panic!("expected a code action: {actions:?}");
+            };
+            assert_eq!(action.title, "Insert a space");
+            assert_eq!(action.kind, Some(CodeActionKind::QUICKFIX));
+            assert_eq!(
+                action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri()],
+                [TextEdit::new(
+                    Range::new(Position::new(2, 1), Position::new(2, 1)),
+                    " ".to_string(),
+                )]
+            );
+        });
+    }
+}
@@ -2,4 +2,7 @@ pub(crate) mod args;
 pub(crate) mod check;
 pub(crate) mod debug_cst;
 pub(crate) mod format;
+pub(crate) mod git;
+pub(crate) mod lsp;
+pub(crate) mod output;
 pub(crate) mod utils;
@@ -0,0 +1,289 @@
+//! Writers of diagnostics in the formats selectable by `--output-format`.
+use cjkfmt_core::diagnostic::Diagnostic;
+use serde_json::{Value, json};
+
+use crate::cli::{args::OutputFormat, utils::format_diagnostic};
+
+/// Metadata of a diagnostic code.
+struct Rule {
+    code: &'static str,
+    name: &'static str,
+    description: &'static str,
+}
+
+/// Metadata of every diagnostic code cjkfmt may report.
+const RULES: &[Rule] = &[
+    Rule {
+        code: "W001",
+        name: "line-too-long",
+        description: "Line length exceeds the maximum width.",
+    },
+    Rule {
+        code: "W002",
+        name: "spacing",
+        description: "Spacing between full-width and half-width characters violates the configured rule.",
+    },
+    Rule {
+        code: "W003",
+        name: "line-start-prohibition",
+        description: "A line of a paragraph starts with a character prohibited at the start of a line.",
+    },
+    Rule {
+        code: "W004",
+        name: "line-end-prohibition",
+        description: "A line of a paragraph ends with a character prohibited at the end of a line.",
+    },
+    Rule {
+        code: "W005",
+        name: "fullwidth-alphanumerics",
+        description: "Full-width alphabets or digits are used where half-width ones are configured.",
+    },
+    Rule {
+        code: "W006",
+        name: "halfwidth-katakana",
+        description: "Half-width katakana are used where full-width ones are configured.",
+    },
+    Rule {
+        code: "W007",
+        name: "fullwidth-space",
+        description: "Full-width spaces are used where ASCII spaces are configured.",
+    },
+    Rule {
+        code: "W008",
+        name: "period-style",
+        description: "A full stop differs from the configured style.",
+    },
+    Rule {
+        code: "W009",
+        name: "comma-style",
+        description: "A comma differs from the configured style.",
+    },
+    Rule {
+        code: "W010",
+        name: "parentheses-style",
+        description: "Half-width parentheses are used around CJK text where full-width ones are configured.",
+    },
+];
+
+/// Writes the diagnostics to `stdout` in the given format.
+pub fn write_diagnostics<W: std::io::Write>(
+    stdout: &mut W,
+    output_format: OutputFormat,
+    diagnostics: &[Diagnostic],
+) -> anyhow::Result<()> {
+    match output_format {
+        OutputFormat::Text => {
+            for diagnostic in diagnostics {
+                writeln!(stdout, "{}", format_diagnostic(diagnostic))?;
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *stdout, diagnostics)?;
+            writeln!(stdout)?;
+        }
+        OutputFormat::Jsonl => {
+            for diagnostic in diagnostics {
+                serde_json::to_writer(&mut *stdout, diagnostic)?;
+                writeln!(stdout)?;
+            }
+        }
+        OutputFormat::Sarif => {
+            serde_json::to_writer_pretty(&mut *stdout, &sarif_log(diagnostics))?;
+            writeln!(stdout)?;
+        }
+        OutputFormat::Github => {
+            for diagnostic in diagnostics {
+                writeln!(stdout, "{}", github_annotation(diagnostic))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds a SARIF 2.1.0 log of the diagnostics.
+///
+/// SARIF positions are one-based and columns are counted in UTF-16 code units
+/// by default, so they map directly onto [`cjkfmt_core::position::Position`].
+fn sarif_log(diagnostics: &[Diagnostic]) -> Value {
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.code,
+                "name": rule.name,
+                "shortDescription": { "text": rule.description },
+            })
+        })
+        .collect();
+    let results: Vec<Value> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let mut physical_location = json!({
+                "region": {
+                    "startLine": diagnostic.start.line + 1,
+                    "startColumn": diagnostic.start.column + 1,
+                    "endLine": diagnostic.end.line + 1,
+                    "endColumn": diagnostic.end.column + 1,
+                },
+            });
+            if let Some(filename) = &diagnostic.filename {
+                physical_location["artifactLocation"] =
+                    json!({ "uri": filename.replace('\\', "/") });
+            }
+            json!({
+                "ruleId": diagnostic.code,
+                "level": "warning",
+                "message": { "text": diagnostic.message },
+                "locations": [{ "physicalLocation": physical_location }],
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cjkfmt",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Returns a GitHub Actions workflow command annotating the diagnostic.
+fn github_annotation(diagnostic: &Diagnostic) -> String {
+    let mut properties = Vec::new();
+    if let Some(filename) = &diagnostic.filename {
+        properties.push(format!("file={}", escape_github_property(filename)));
+    }
+    properties.push(format!("line={}", diagnostic.start.line + 1));
+    properties.push(format!("col={}", diagnostic.start.column + 1));
+    properties.push(format!("endLine={}", diagnostic.end.line + 1));
+    properties.push(format!("endColumn={}", diagnostic.end.column + 1));
+    properties.push(format!(
+        "title={}",
+        escape_github_property(&diagnostic.code)
+    ));
+    format!(
+        "::warning {}::{}",
+        properties.join(","),
+        escape_github_data(&diagnostic.message)
+    )
+}
+
+fn escape_github_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_github_property(text: &str) -> String {
+    escape_github_data(text)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use cjkfmt_core::position::Position;
+
+    use super::*;
+
+    fn diagnostics() -> Vec<Diagnostic> {
+        vec![
+            Diagnostic::new(
+                Some("docs/a.md"),
+                Position::new(0, 3),
+                Position::new(0, 4),
+                "W002".to_string(),
+                "Possible spacing position found".to_string(),
+            ),
+            Diagnostic::new(
+                None::<String>,
+                Position::new(2, 40),
+                Position::new(2, 41),
+                "W001".to_string(),
+                "Line length exceeds 80 characters".to_string(),
+            ),
+        ]
+    }
+
+    fn output(output_format: OutputFormat) -> String {
+        let mut output = Vec::new();
+        write_diagnostics(&mut output, output_format, &diagnostics()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn json_is_an_array_of_diagnostics() {
+        let actual: Vec<Diagnostic> = serde_json::from_str(&output(OutputFormat::Json)).unwrap();
+        assert_eq!(actual, diagnostics());
+    }
+
+    #[test]
+    fn jsonl_is_a_diagnostic_per_line() {
+        let actual: Vec<Diagnostic> = output(OutputFormat::Jsonl)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(actual, diagnostics());
+    }
+
+    #[test]
+    fn sarif_has_rules_and_one_based_locations() {
+        let log: Value = serde_json::from_str(&output(OutputFormat::Sarif)).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+
+        let run = &log["runs"][0];
+        let rule_ids: Vec<&str> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            rule_ids,
+            [
+                "W001", "W002", "W003", "W004", "W005", "W006", "W007", "W008", "W009", "W010"
+            ]
+        );
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "W002");
+        assert_eq!(result["message"]["text"], "Possible spacing position found");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "docs/a.md");
+        assert_eq!(location["region"]["startLine"], 1);
+        assert_eq!(location["region"]["startColumn"], 4);
+        assert_eq!(location["region"]["endColumn"], 5);
+
+        let location = &run["results"][1]["locations"][0]["physicalLocation"];
+        assert!(location.get("artifactLocation").is_none());
+        assert_eq!(location["region"]["startLine"], 3);
+    }
+
+    #[test]
+    fn github_emits_workflow_commands() {
+        assert_eq!(
+            output(OutputFormat::Github),
+            concat!(
+                "::warning file=docs/a.md,line=1,col=4,endLine=1,endColumn=5,title=W002",
+                "::Possible spacing position found\n",
+                "::warning line=3,col=41,endLine=3,endColumn=42,title=W001",
+                "::Line length exceeds 80 characters\n",
+            )
+        );
+    }
+
+    #[test]
+    fn github_escapes_special_characters() {
+        assert_eq!(escape_github_data("50%\r\n"), "50%25%0D%0A");
+        assert_eq!(escape_github_property("C:\\a,b.md"), "C%3A\\a%2Cb.md");
+    }
+}
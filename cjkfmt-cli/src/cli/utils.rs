@@ -1,6 +1,15 @@
+use std::path::{Path, PathBuf};
+
 use cjkfmt_core::diagnostic::Diagnostic;
+use cjkfmt_parser::detect_grammar;
+use ignore::WalkBuilder;
 use yansi::Paint;
 
+/// Name of the ignore file dedicated to cjkfmt.
+///
+/// It uses the same syntax as `.gitignore` and takes precedence over it.
+const IGNORE_FILENAME: &str = ".cjkfmtignore";
+
 /// Returns a printable string representation of the diagnostic.
 pub fn format_diagnostic(diagnostic: &Diagnostic) -> String {
     let filename = diagnostic.filename.as_deref().unwrap_or("<stdin>");
@@ -19,3 +28,91 @@ pub fn format_diagnostic(diagnostic: &Diagnostic) -> String {
         colon, colon, colon
     )
 }
+
+/// Expands the given paths into the files to process.
+///
+/// Paths to files are returned as given. Directories are walked recursively,
+/// selecting files of a supported grammar and skipping hidden files and files
+/// ignored by `.gitignore`, `.ignore`, or `.cjkfmtignore`.
+pub fn collect_files<P: AsRef<Path>>(paths: &[P]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        if !path.is_dir() {
+            files.push(path.to_path_buf());
+            continue;
+        }
+
+        let mut found = Vec::new();
+        let walker = WalkBuilder::new(path)
+            .require_git(false)
+            .add_custom_ignore_filename(IGNORE_FILENAME)
+            .build();
+        for entry in walker {
+            let entry = entry?;
+            if entry.file_type().is_some_and(|t| t.is_file())
+                && detect_grammar(entry.path()).is_some()
+            {
+                found.push(entry.into_path());
+            }
+        }
+        // Walking order depends on the file system, so sort for stable output
+        found.sort();
+        files.extend(found);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn collect_files_walks_directories_and_honours_ignore_files() {
+        let directory = tempdir().unwrap();
+        let root = directory.path();
+        for path in [
+            "a.md",
+            "b.json",
+            "c.txt",
+            "sub/d.markdown",
+            "sub/e.md",
+            "gitignored/f.md",
+            "ignored.md",
+            "cjkfmtignored.md",
+            ".hidden/g.md",
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::write(root.join(".gitignore"), "gitignored/\n").unwrap();
+        fs::write(root.join(".ignore"), "ignored.md\n").unwrap();
+        fs::write(root.join("sub/.cjkfmtignore"), "e.md\n").unwrap();
+        fs::write(root.join(".cjkfmtignore"), "cjkfmtignored.md\n").unwrap();
+
+        let files = collect_files(&[root]).unwrap();
+
+        let expected: Vec<PathBuf> = ["a.md", "b.json", "sub/d.markdown"]
+            .iter()
+            .map(|path| root.join(path))
+            .collect();
+        assert_eq!(files, expected);
+    }
+
+    #[test]
+    fn collect_files_keeps_explicit_files_as_given() {
+        let directory = tempdir().unwrap();
+        let text = directory.path().join("notes.txt");
+        fs::write(&text, "").unwrap();
+        let missing = directory.path().join("missing.md");
+
+        let files = collect_files(&[&text, &missing]).unwrap();
+
+        assert_eq!(files, [text, missing]);
+    }
+}
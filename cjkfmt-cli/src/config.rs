@@ -1,4 +1,4 @@
-use std::{env, path::PathBuf};
+use std::{env, fmt, ops::RangeInclusive, path::PathBuf, str::FromStr};
 
 use clap::ValueEnum;
 use figment::{
@@ -6,8 +6,16 @@ use figment::{
     providers::{Env, Format, Json, Serialized},
 };
 use serde::{Deserialize, Serialize};
+use unicode_script::{Script, UnicodeScript};
+use unicode_segmentation::UnicodeSegmentation;
 
-use crate::args::CliArgs;
+use crate::{
+    args::CliArgs,
+    line_break::{
+        PROHIBITED_END, PROHIBITED_END_JIS_X_4051, PROHIBITED_START, PROHIBITED_START_LOOSE,
+        PROHIBITED_START_STRICT,
+    },
+};
 
 /// The configuration for cjkfmt.
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,8 +27,23 @@ pub struct Config {
     /// Maximum line width to allow. (default: 80)
     pub max_width: u32,
 
+    /// How to wrap lines of Markdown paragraphs.
+    pub wrap: WrapMode,
+
+    /// Line ending to write when formatting.
+    pub line_ending: LineEnding,
+
     /// Rules for handling spaces between full-width and half-width characters.
     pub spacing: SpacingConfig,
+
+    /// Rules for normalizing full-width and half-width variants of characters.
+    pub normalize: NormalizeConfig,
+
+    /// Rules for the style of punctuation.
+    pub punctuation: PunctuationConfig,
+
+    /// Rules for choosing where to break lines.
+    pub line_break: LineBreakConfig,
 }
 
 impl Config {
@@ -40,12 +63,13 @@ impl Config {
         // 2. JSON file `.cjkfmt.json` at the user's configuration directory
         //    (`XDG_CONFIG_HOME` if set, otherwise `$HOME/.config`)
         // 3. JSON file `.cjkfmt.json` found in the current or ancestor directories
-        // 4. Environment variables prefixed with `CJKFMT_`
+        // 4. Environment variables prefixed with `CJKFMT_`, with `__` between
+        //    the names of nested options
         let config = Figment::new();
         let config = config.merge(Serialized::defaults(Config::default()));
         let config = user_config_path.map_or(config.clone(), |p| config.merge(Json::file_exact(p)));
         let config = config.merge(Json::file(".cjkfmt.json"));
-        let config = config.merge(Env::prefixed("CJKFMT_"));
+        let config = config.merge(Env::prefixed("CJKFMT_").split("__"));
         let config = config.merge(args);
         let config: Self = config.extract()?;
 
@@ -58,11 +82,44 @@ impl Default for Config {
         Config {
             ambiguous_width: AmbiguousWidth::Wide,
             max_width: 80,
+            wrap: WrapMode::Split,
+            line_ending: LineEnding::Auto,
             spacing: Default::default(),
+            normalize: Default::default(),
+            punctuation: Default::default(),
+            line_break: Default::default(),
         }
     }
 }
 
+/// How to wrap lines of Markdown paragraphs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+    /// Split lines exceeding the maximum width, keeping the existing line breaks.
+    Split,
+
+    /// Join the soft-wrapped lines of each paragraph and wrap them again.
+    Reflow,
+}
+
+/// Line ending to write when formatting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    /// Keep the existing line endings and use the most common one for new lines.
+    Auto,
+
+    /// Use LF for every line.
+    Lf,
+
+    /// Use CR+LF for every line.
+    Crlf,
+
+    /// Use the line ending of the platform: CR+LF on Windows and LF elsewhere.
+    Native,
+}
+
 /// Rules for handling spaces between full-width and half-width characters.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
 #[serde(rename_all = "snake_case")]
@@ -86,8 +143,19 @@ pub struct SpacingConfig {
 
     /// How to handle spaces between full-width and half-width digits.
     pub digits: SpacingRule,
-    // /// Whether to treat full-width punctuation as full-width characters or not.
-    // pub punctuation_as_fullwidth: bool, // TODO: Implement this option
+
+    /// Whether to treat full-width punctuation as full-width characters or not.
+    pub punctuation_as_fullwidth: bool,
+
+    /// How to handle spaces between code spans and full-width characters.
+    pub inline_code: SpacingRule,
+
+    /// Whether to apply the spacing rules across emphasis delimiters and link
+    /// syntax in Markdown or not.
+    pub markup_boundaries: bool,
+
+    /// Changes to the characters in each class of the spacing rules.
+    pub classes: CharacterClasses,
 }
 
 impl Default for SpacingConfig {
@@ -95,6 +163,452 @@ impl Default for SpacingConfig {
         SpacingConfig {
             alphabets: SpacingRule::Ignore,
             digits: SpacingRule::Ignore,
+            punctuation_as_fullwidth: false,
+            inline_code: SpacingRule::Ignore,
+            markup_boundaries: false,
+            classes: CharacterClasses::default(),
+        }
+    }
+}
+
+/// Changes to the characters in each class of the spacing rules.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CharacterClasses {
+    /// Characters of the CJK class, replacing or modifying the default ones.
+    pub cjk: Option<ClassMembers>,
+
+    /// Characters of the Latin class, replacing or modifying the default ones.
+    pub latin: Option<ClassMembers>,
+
+    /// Characters of the Digit class, replacing or modifying the default ones.
+    pub digit: Option<ClassMembers>,
+}
+
+/// Characters of a class given in the configuration.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ClassMembersSource", into = "ClassMembersSource")]
+pub enum ClassMembers {
+    /// Use exactly the characters matching the entries.
+    Replace(Vec<ClassEntry>),
+
+    /// Add and remove characters to and from the default ones.
+    Modify {
+        add: Vec<ClassEntry>,
+        remove: Vec<ClassEntry>,
+    },
+}
+
+impl ClassMembers {
+    /// Returns whether the configuration puts a character in the class
+    /// explicitly.
+    pub fn includes(&self, c: char) -> bool {
+        let (ClassMembers::Replace(entries) | ClassMembers::Modify { add: entries, .. }) = self;
+        entries.iter().any(|entry| entry.matches(c))
+    }
+
+    /// Returns whether the configuration takes a character out of the class
+    /// it belongs to by default.
+    pub fn excludes(&self, c: char) -> bool {
+        match self {
+            ClassMembers::Replace(_) => true,
+            ClassMembers::Modify { remove, .. } => remove.iter().any(|entry| entry.matches(c)),
+        }
+    }
+}
+
+/// The form of [`ClassMembers`] in configuration files, validated when loaded.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ClassMembersSource {
+    Replace(Vec<String>),
+    Modify(ClassChangesSource),
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ClassChangesSource {
+    add: Vec<String>,
+    remove: Vec<String>,
+}
+
+impl TryFrom<ClassMembersSource> for ClassMembers {
+    type Error = String;
+
+    fn try_from(source: ClassMembersSource) -> Result<Self, Self::Error> {
+        let parse = |entries: Vec<String>| -> Result<Vec<ClassEntry>, String> {
+            entries.iter().map(|entry| entry.parse()).collect()
+        };
+        Ok(match source {
+            ClassMembersSource::Replace(entries) => ClassMembers::Replace(parse(entries)?),
+            ClassMembersSource::Modify(ClassChangesSource { add, remove }) => {
+                ClassMembers::Modify {
+                    add: parse(add)?,
+                    remove: parse(remove)?,
+                }
+            }
+        })
+    }
+}
+
+impl From<ClassMembers> for ClassMembersSource {
+    fn from(members: ClassMembers) -> Self {
+        let format = |entries: Vec<ClassEntry>| entries.iter().map(ToString::to_string).collect();
+        match members {
+            ClassMembers::Replace(entries) => ClassMembersSource::Replace(format(entries)),
+            ClassMembers::Modify { add, remove } => {
+                ClassMembersSource::Modify(ClassChangesSource {
+                    add: format(add),
+                    remove: format(remove),
+                })
+            }
+        }
+    }
+}
+
+/// Characters given in the configuration of a character class.
+///
+/// In configuration files, an entry is a code point such as `U+0391`, a range
+/// of code points such as `U+0391..U+03A9`, a Unicode script such as
+/// `script:Greek`, or otherwise the characters of the string themselves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClassEntry {
+    /// The code points in a range.
+    Range(RangeInclusive<char>),
+
+    /// The characters whose Script property is a script.
+    Script(Script),
+
+    /// The characters of a string.
+    Characters(String),
+}
+
+impl ClassEntry {
+    /// Returns whether a character matches the entry.
+    pub fn matches(&self, c: char) -> bool {
+        match self {
+            ClassEntry::Range(range) => range.contains(&c),
+            ClassEntry::Script(script) => c.script() == *script,
+            ClassEntry::Characters(characters) => characters.contains(c),
+        }
+    }
+}
+
+impl FromStr for ClassEntry {
+    type Err = String;
+
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        if let Some(name) = entry.strip_prefix("script:") {
+            return Script::from_full_name(name)
+                .or_else(|| Script::from_short_name(name))
+                .map(ClassEntry::Script)
+                .ok_or_else(|| format!("unknown Unicode script `{name}`"));
+        }
+        if entry.starts_with("U+") {
+            let (start, end) = entry.split_once("..").unwrap_or((entry, entry));
+            let (start, end) = (code_point(start)?, code_point(end)?);
+            if start > end {
+                return Err(format!("empty code point range `{entry}`"));
+            }
+            return Ok(ClassEntry::Range(start..=end));
+        }
+        if entry.is_empty() {
+            return Err("empty character class entry".to_string());
+        }
+        Ok(ClassEntry::Characters(entry.to_string()))
+    }
+}
+
+impl fmt::Display for ClassEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassEntry::Range(range) if range.start() == range.end() => {
+                write!(f, "U+{:04X}", *range.start() as u32)
+            }
+            ClassEntry::Range(range) => {
+                write!(
+                    f,
+                    "U+{:04X}..U+{:04X}",
+                    *range.start() as u32,
+                    *range.end() as u32
+                )
+            }
+            ClassEntry::Script(script) => write!(f, "script:{}", script.full_name()),
+            ClassEntry::Characters(characters) => f.write_str(characters),
+        }
+    }
+}
+
+/// Parses a code point written as `U+` followed by hexadecimal digits.
+fn code_point(text: &str) -> Result<char, String> {
+    text.strip_prefix("U+")
+        .filter(|digits| (4..=6).contains(&digits.len()))
+        .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+        .and_then(char::from_u32)
+        .ok_or_else(|| format!("invalid code point `{text}`"))
+}
+
+/// Configuration for normalizing full-width and half-width variants of characters.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NormalizeConfig {
+    /// How to handle full-width alphabets and digits such as `Ａ` and `１`.
+    pub fullwidth_alphanumerics: FullwidthAlphanumerics,
+
+    /// How to handle half-width katakana such as `ｶﾀｶﾅ`.
+    pub halfwidth_katakana: HalfwidthKatakana,
+
+    /// How to handle full-width (ideographic) spaces.
+    pub fullwidth_space: FullwidthSpace,
+}
+
+impl Default for NormalizeConfig {
+    fn default() -> Self {
+        NormalizeConfig {
+            fullwidth_alphanumerics: FullwidthAlphanumerics::Keep,
+            halfwidth_katakana: HalfwidthKatakana::Keep,
+            fullwidth_space: FullwidthSpace::Keep,
+        }
+    }
+}
+
+/// How to handle full-width alphabets and digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum FullwidthAlphanumerics {
+    /// Leave full-width alphabets and digits as they are.
+    Keep,
+
+    /// Replace full-width alphabets and digits with ASCII ones.
+    ToHalfwidth,
+}
+
+/// How to handle half-width katakana.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum HalfwidthKatakana {
+    /// Leave half-width katakana as they are.
+    Keep,
+
+    /// Replace half-width katakana with full-width ones.
+    ToFullwidth,
+}
+
+/// How to handle full-width (ideographic) spaces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum FullwidthSpace {
+    /// Leave full-width spaces as they are.
+    Keep,
+
+    /// Replace each full-width space with an ASCII space.
+    ToAscii,
+}
+
+/// Configuration for the style of punctuation.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PunctuationConfig {
+    /// Full stop to use in place of the others.
+    pub period: Period,
+
+    /// Comma to use in place of the others.
+    pub comma: Comma,
+
+    /// How to handle parentheses around CJK text.
+    pub parentheses: Parentheses,
+}
+
+impl Default for PunctuationConfig {
+    fn default() -> Self {
+        PunctuationConfig {
+            period: Period::Keep,
+            comma: Comma::Keep,
+            parentheses: Parentheses::Keep,
+        }
+    }
+}
+
+/// Full stop to use in place of the others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    /// Leave full stops as they are.
+    Keep,
+
+    /// Use the ideographic full stop `。`.
+    Ideographic,
+
+    /// Use the full-width full stop `．`.
+    Fullwidth,
+}
+
+/// Comma to use in place of the others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Comma {
+    /// Leave commas as they are.
+    Keep,
+
+    /// Use the ideographic comma `、`.
+    Ideographic,
+
+    /// Use the full-width comma `，`.
+    Fullwidth,
+}
+
+/// How to handle parentheses around CJK text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum Parentheses {
+    /// Leave parentheses as they are.
+    Keep,
+
+    /// Replace ASCII parentheses with full-width ones when they enclose or
+    /// adjoin CJK text.
+    FullwidthAroundCjk,
+}
+
+/// Configuration for line breaking.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineBreakConfig {
+    /// The sets of characters prohibited at the start and the end of a line (kinsoku) to begin with.
+    pub preset: KinsokuPreset,
+
+    /// Characters prohibited at the start of a line, replacing or modifying the preset's set.
+    pub prohibited_start: Option<CharacterSet>,
+
+    /// Characters prohibited at the end of a line, replacing or modifying the preset's set.
+    pub prohibited_end: Option<CharacterSet>,
+
+    /// Whether a full stop or a comma may hang past the maximum width (burasage).
+    pub hanging_punctuation: bool,
+
+    /// How to resolve a line break prohibited by the kinsoku rule.
+    pub strategy: KinsokuStrategy,
+
+    /// How far characters pushed in may exceed the maximum width.
+    pub push_in_tolerance: u32,
+
+    /// Additional sequences which must not be split across lines.
+    pub inseparable: Vec<String>,
+}
+
+impl Default for LineBreakConfig {
+    fn default() -> Self {
+        Self {
+            preset: KinsokuPreset::default(),
+            prohibited_start: None,
+            prohibited_end: None,
+            hanging_punctuation: false,
+            strategy: KinsokuStrategy::default(),
+            push_in_tolerance: 2,
+            inseparable: Vec::new(),
+        }
+    }
+}
+
+impl LineBreakConfig {
+    /// Returns the grapheme clusters prohibited at the start of a line.
+    pub fn prohibited_start(&self) -> String {
+        let preset = match self.preset {
+            KinsokuPreset::Standard => PROHIBITED_START,
+            KinsokuPreset::Strict => PROHIBITED_START_STRICT,
+            KinsokuPreset::Loose => PROHIBITED_START_LOOSE,
+        };
+        CharacterSet::resolve(self.prohibited_start.as_ref(), preset)
+    }
+
+    /// Returns the grapheme clusters prohibited at the end of a line.
+    pub fn prohibited_end(&self) -> String {
+        let preset = match self.preset {
+            KinsokuPreset::Standard => PROHIBITED_END,
+            KinsokuPreset::Strict | KinsokuPreset::Loose => PROHIBITED_END_JIS_X_4051,
+        };
+        CharacterSet::resolve(self.prohibited_end.as_ref(), preset)
+    }
+}
+
+/// Named sets of characters prohibited at the start and the end of a line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum KinsokuPreset {
+    /// The sets cjkfmt has always used.
+    #[default]
+    Standard,
+
+    /// The strict rule of JIS X 4051: small kana, the prolonged sound mark,
+    /// iteration marks and hyphens cannot start a line either.
+    Strict,
+
+    /// The loose rule of JIS X 4051: only closing brackets and punctuation
+    /// marks cannot start a line.
+    Loose,
+}
+
+/// How to resolve a line break prohibited by the kinsoku rule.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum KinsokuStrategy {
+    /// Move characters to the next line until the line can be broken (oidashi).
+    #[default]
+    PushOut,
+
+    /// Keep characters prohibited at the start of a line on the current line
+    /// if they fit within the tolerance (oikomi), and push out otherwise.
+    PushIn,
+
+    /// Push in or push out, whichever makes the two lines more balanced.
+    MinRaggedness,
+}
+
+/// A set of characters given in the configuration.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CharacterSet {
+    /// Use exactly the characters in the string.
+    Replace(String),
+
+    /// Add and remove characters to and from the preset's set.
+    Modify(CharacterSetChanges),
+}
+
+/// Characters to add to and remove from a preset's set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CharacterSetChanges {
+    /// Characters to add to the set.
+    pub add: String,
+
+    /// Characters to remove from the set.
+    pub remove: String,
+}
+
+impl CharacterSet {
+    /// Applies the configured set, if any, to the grapheme clusters of `preset`.
+    fn resolve(set: Option<&CharacterSet>, preset: &str) -> String {
+        match set {
+            None => preset.to_string(),
+            Some(CharacterSet::Replace(characters)) => characters.clone(),
+            Some(CharacterSet::Modify(CharacterSetChanges { add, remove })) => {
+                let removed: Vec<&str> = remove.graphemes(true).collect();
+                let mut graphemes: Vec<&str> = preset
+                    .graphemes(true)
+                    .filter(|grapheme| !removed.contains(grapheme))
+                    .collect();
+                for grapheme in add.graphemes(true) {
+                    if !graphemes.contains(&grapheme) {
+                        graphemes.push(grapheme);
+                    }
+                }
+                graphemes.concat()
+            }
         }
     }
 }
@@ -235,4 +749,349 @@ mod tests {
             None => assert!(result.is_err(), "non-snake_case value should be rejected"),
         }
     }
+
+    #[rstest]
+    // "snake_case" is the documented, canonical form (ADR-0001).
+    #[case("auto", Some(LineEnding::Auto))]
+    #[case("lf", Some(LineEnding::Lf))]
+    #[case("crlf", Some(LineEnding::Crlf))]
+    #[case("native", Some(LineEnding::Native))]
+    #[case("CRLF", None)]
+    #[case("Lf", None)]
+    fn line_ending_accepts_only_snake_case_value(
+        #[case] value: &str,
+        #[case] expected: Option<LineEnding>,
+    ) {
+        let result: Result<Config, _> = Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(&format!(r#"{{ "line_ending": "{value}" }}"#)))
+            .extract();
+
+        match expected {
+            Some(expected) => assert_eq!(
+                result
+                    .expect("the documented snake_case value should deserialize")
+                    .line_ending,
+                expected
+            ),
+            None => assert!(result.is_err(), "non-snake_case value should be rejected"),
+        }
+    }
+
+    #[rstest]
+    // "snake_case" is the documented, canonical form (ADR-0001).
+    #[case("split", Some(WrapMode::Split))]
+    #[case("reflow", Some(WrapMode::Reflow))]
+    #[case("Split", None)]
+    #[case("Reflow", None)]
+    fn wrap_mode_accepts_only_snake_case_value(
+        #[case] value: &str,
+        #[case] expected: Option<WrapMode>,
+    ) {
+        let result: Result<Config, _> = Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(&format!(r#"{{ "wrap": "{value}" }}"#)))
+            .extract();
+
+        match expected {
+            Some(expected) => assert_eq!(
+                result
+                    .expect("the documented snake_case value should deserialize")
+                    .wrap,
+                expected
+            ),
+            None => assert!(result.is_err(), "non-snake_case value should be rejected"),
+        }
+    }
+
+    #[rstest]
+    // "snake_case" is the documented, canonical form (ADR-0001).
+    #[case("standard", Some(KinsokuPreset::Standard))]
+    #[case("strict", Some(KinsokuPreset::Strict))]
+    #[case("loose", Some(KinsokuPreset::Loose))]
+    #[case("Strict", None)]
+    #[case("Loose", None)]
+    fn kinsoku_preset_accepts_only_snake_case_value(
+        #[case] value: &str,
+        #[case] expected: Option<KinsokuPreset>,
+    ) {
+        let result: Result<Config, _> = Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(&format!(
+                r#"{{ "line_break": {{ "preset": "{value}" }} }}"#
+            )))
+            .extract();
+
+        match expected {
+            Some(expected) => assert_eq!(
+                result
+                    .expect("the documented snake_case value should deserialize")
+                    .line_break
+                    .preset,
+                expected
+            ),
+            None => assert!(result.is_err(), "non-snake_case value should be rejected"),
+        }
+    }
+
+    fn line_break_config(json: &str) -> LineBreakConfig {
+        let config: Config = Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(json))
+            .extract()
+            .expect("the line_break configuration should deserialize");
+        config.line_break
+    }
+
+    #[test]
+    fn prohibited_sets_default_to_the_standard_preset() {
+        let line_break = line_break_config("{}");
+
+        assert_eq!(line_break.prohibited_start(), PROHIBITED_START);
+        assert_eq!(line_break.prohibited_end(), PROHIBITED_END);
+    }
+
+    #[test]
+    fn prohibited_sets_follow_the_preset() {
+        let strict = line_break_config(r#"{ "line_break": { "preset": "strict" } }"#);
+        let loose = line_break_config(r#"{ "line_break": { "preset": "loose" } }"#);
+
+        assert!(strict.prohibited_start().contains('ゃ'));
+        assert!(!loose.prohibited_start().contains('ゃ'));
+        assert!(loose.prohibited_start().contains('」'));
+        assert_eq!(strict.prohibited_end(), loose.prohibited_end());
+    }
+
+    #[test]
+    fn prohibited_set_string_replaces_the_preset() {
+        let line_break = line_break_config(
+            r#"{ "line_break": { "preset": "strict", "prohibited_start": "」。" } }"#,
+        );
+
+        assert_eq!(line_break.prohibited_start(), "」。");
+        assert_eq!(line_break.prohibited_end(), PROHIBITED_END_JIS_X_4051);
+    }
+
+    #[test]
+    fn prohibited_set_object_adds_and_removes_characters() {
+        let line_break = line_break_config(
+            r#"{
+                "line_break": {
+                    "prohibited_end": { "add": "＄（", "remove": "([" }
+                }
+            }"#,
+        );
+
+        assert_eq!(
+            line_break.prohibited_end(),
+            "｛〔〈《「『【〘〖〝'\"｟«＄（"
+        );
+    }
+
+    #[test]
+    fn prohibited_set_object_rejects_unknown_keys() {
+        let result: Result<Config, _> = Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(
+                r#"{ "line_break": { "prohibited_end": { "append": "＄" } } }"#,
+            ))
+            .extract();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn normalization_keeps_characters_by_default() {
+        let config = Config::default();
+        assert_eq!(
+            config.normalize.fullwidth_alphanumerics,
+            FullwidthAlphanumerics::Keep
+        );
+        assert_eq!(config.normalize.halfwidth_katakana, HalfwidthKatakana::Keep);
+        assert_eq!(config.normalize.fullwidth_space, FullwidthSpace::Keep);
+    }
+
+    #[rstest]
+    // "snake_case" is the documented, canonical form (ADR-0001).
+    #[case(r#""fullwidth_alphanumerics": "to_halfwidth""#, true)]
+    #[case(r#""halfwidth_katakana": "to_fullwidth""#, true)]
+    #[case(r#""fullwidth_space": "to_ascii""#, true)]
+    #[case(r#""fullwidth_alphanumerics": "ToHalfwidth""#, false)]
+    #[case(r#""halfwidth_katakana": "to-fullwidth""#, false)]
+    #[case(r#""fullwidth_space": "to_halfwidth""#, false)]
+    fn normalize_rules_accept_only_snake_case_value(#[case] entry: &str, #[case] valid: bool) {
+        let result: Result<Config, _> = Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(&format!(
+                r#"{{ "normalize": {{ {entry} }} }}"#
+            )))
+            .extract();
+
+        assert_eq!(
+            result.is_ok(),
+            valid,
+            "only snake_case values should deserialize"
+        );
+    }
+
+    #[rstest]
+    #[case(r#""period": "ideographic""#, true)]
+    #[case(r#""comma": "fullwidth""#, true)]
+    #[case(r#""parentheses": "fullwidth_around_cjk""#, true)]
+    #[case(r#""period": "。""#, false)]
+    #[case(r#""comma": "Ideographic""#, false)]
+    #[case(r#""parentheses": "fullwidth-around-cjk""#, false)]
+    fn punctuation_styles_accept_only_snake_case_value(#[case] entry: &str, #[case] valid: bool) {
+        let result: Result<Config, _> = Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(&format!(
+                r#"{{ "punctuation": {{ {entry} }} }}"#
+            )))
+            .extract();
+
+        assert_eq!(
+            result.is_ok(),
+            valid,
+            "only snake_case values should deserialize"
+        );
+    }
+
+    #[rstest]
+    #[case("U+0391", Ok(ClassEntry::Range('Α'..='Α')))]
+    #[case("U+0391..U+03a9", Ok(ClassEntry::Range('Α'..='Ω')))]
+    #[case("U+20000..U+2A6DF", Ok(ClassEntry::Range('\u{20000}'..='\u{2A6DF}')))]
+    #[case("script:Greek", Ok(ClassEntry::Script(Script::Greek)))]
+    #[case("script:Cyrl", Ok(ClassEntry::Script(Script::Cyrillic)))]
+    #[case("０１２", Ok(ClassEntry::Characters("０１２".to_string())))]
+    #[case("U+91", Err("invalid code point `U+91`"))]
+    #[case("U+D800", Err("invalid code point `U+D800`"))]
+    #[case("U+03A9..U+0391", Err("empty code point range `U+03A9..U+0391`"))]
+    #[case("U+0391..", Err("invalid code point ``"))]
+    #[case("script:Klingon", Err("unknown Unicode script `Klingon`"))]
+    #[case("", Err("empty character class entry"))]
+    fn class_entries_are_parsed(#[case] entry: &str, #[case] expected: Result<ClassEntry, &str>) {
+        assert_eq!(entry.parse(), expected.map_err(str::to_string));
+    }
+
+    #[rstest]
+    #[case("U+0391")]
+    #[case("U+0391..U+03A9")]
+    #[case("script:Greek")]
+    #[case("αβ")]
+    fn class_entries_are_written_as_parsed(#[case] entry: &str) {
+        assert_eq!(entry.parse::<ClassEntry>().unwrap().to_string(), entry);
+    }
+
+    #[rstest]
+    #[case(r#"{ "latin": ["U+0041..U+005A"] }"#, None)]
+    #[case(r#"{ "latin": { "add": ["script:Greek"], "remove": ["×"] } }"#, None)]
+    #[case(
+        r#"{ "digit": { "add": ["U+FF10..U+FF1G"] } }"#,
+        Some("invalid code point")
+    )]
+    #[case(r#"{ "cjk": ["script:Klingon"] }"#, Some("unknown Unicode script"))]
+    #[case(r#"{ "kana": ["あ"] }"#, Some("unknown field"))]
+    fn character_classes_are_validated_when_loaded(
+        #[case] classes: &str,
+        #[case] error: Option<&str>,
+    ) {
+        let result: Result<Config, _> = Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(&format!(
+                r#"{{ "spacing": {{ "classes": {classes} }} }}"#
+            )))
+            .extract();
+
+        match error {
+            None => assert!(result.is_ok(), "{result:?}"),
+            Some(error) => {
+                let message = result
+                    .expect_err("invalid classes should be rejected")
+                    .to_string();
+                assert!(message.contains(error), "{message}");
+            }
+        }
+    }
+
+    #[test]
+    fn markup_spacing_rules_are_disabled_by_default() {
+        let config = Config::default();
+        assert_eq!(config.spacing.inline_code, SpacingRule::Ignore);
+        assert!(!config.spacing.markup_boundaries);
+    }
+
+    #[test]
+    fn punctuation_as_fullwidth_is_disabled_by_default() {
+        let config = |json: &str| -> Config {
+            Figment::new()
+                .merge(Serialized::defaults(Config::default()))
+                .merge(Json::string(json))
+                .extract()
+                .expect("the spacing configuration should deserialize")
+        };
+
+        assert!(!config("{}").spacing.punctuation_as_fullwidth);
+        assert!(
+            config(r#"{ "spacing": { "punctuation_as_fullwidth": true } }"#)
+                .spacing
+                .punctuation_as_fullwidth
+        );
+    }
+
+    #[test]
+    fn hanging_punctuation_is_disabled_by_default() {
+        assert!(!line_break_config("{}").hanging_punctuation);
+        assert!(
+            line_break_config(r#"{ "line_break": { "hanging_punctuation": true } }"#)
+                .hanging_punctuation
+        );
+    }
+
+    #[rstest]
+    // "snake_case" is the documented, canonical form (ADR-0001).
+    #[case("push_out", Some(KinsokuStrategy::PushOut))]
+    #[case("push_in", Some(KinsokuStrategy::PushIn))]
+    #[case("min_raggedness", Some(KinsokuStrategy::MinRaggedness))]
+    #[case("PushIn", None)]
+    #[case("pushIn", None)]
+    fn kinsoku_strategy_accepts_only_snake_case_value(
+        #[case] value: &str,
+        #[case] expected: Option<KinsokuStrategy>,
+    ) {
+        let result: Result<Config, _> = Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(&format!(
+                r#"{{ "line_break": {{ "strategy": "{value}" }} }}"#
+            )))
+            .extract();
+
+        match expected {
+            Some(expected) => assert_eq!(
+                result
+                    .expect("the documented snake_case value should deserialize")
+                    .line_break
+                    .strategy,
+                expected
+            ),
+            None => assert!(result.is_err(), "non-snake_case value should be rejected"),
+        }
+    }
+
+    #[test]
+    fn kinsoku_strategy_defaults_to_push_out() {
+        let line_break = line_break_config("{}");
+
+        assert_eq!(line_break.strategy, KinsokuStrategy::PushOut);
+        assert_eq!(line_break.push_in_tolerance, 2);
+    }
+
+    #[test]
+    fn inseparable_sequences_are_empty_by_default() {
+        assert!(line_break_config("{}").inseparable.is_empty());
+        assert_eq!(
+            line_break_config(r#"{ "line_break": { "inseparable": ["C++", "株式会社"] } }"#)
+                .inseparable,
+            ["C++", "株式会社"]
+        );
+    }
 }
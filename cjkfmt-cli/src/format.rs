@@ -1,43 +1,151 @@
+use std::ops::Range;
+
 use crate::{
-    config::Config,
+    config::{Config, LineEnding, WrapMode},
+    document::Document,
     line_break::{BreakPoint, LineBreaker},
-    markdown_spacing::apply_markdown_spacing,
+    markdown_spacing::{apply_markdown_normalization, apply_markdown_spacing},
+    markdown_wrap::{join_paragraph_lines, wrappable_ranges},
+    spacing::TextEdit,
+    suppression::Suppressions,
 };
-use cjkfmt_core::lines_inclusive::LinesInclusiveExt;
+use cjkfmt_core::{lines_inclusive::LinesInclusiveExt, position::Position};
+use cjkfmt_parser::{Grammar, parse};
+use similar::{DiffTag, TextDiff};
 
 pub(crate) fn format_one_file<W: std::io::Write>(
     stdout: &mut W,
     config: &Config,
-    apply_spacing: bool,
+    markdown: bool,
     content: &str,
 ) -> Result<(), anyhow::Error> {
-    // Keep Markdown spacing selection separate from line wrapping. Both
-    // Markdown and non-Markdown inputs retain the existing wrapping pass.
-    let content = if apply_spacing {
-        apply_markdown_spacing(config, content)?
+    // Keep Markdown spacing selection separate from line wrapping. Characters
+    // are normalized first so that the spacing rules see the final ones.
+    let content = if markdown {
+        let content = apply_markdown_normalization(config, content)?;
+        apply_markdown_spacing(config, &content)?
     } else {
         content.to_owned()
     };
 
+    // Reflowing joins soft-wrapped paragraph lines first and lets the
+    // wrapping pass below break them again.
+    let content = if markdown && config.wrap == WrapMode::Reflow {
+        join_paragraph_lines(config, &content)?
+    } else {
+        content
+    };
+
+    // Lines on which W001 is suppressed by a comment, including every line of
+    // an ignored file, are not wrapped.
+    let suppressions = if markdown {
+        let tree = parse(Grammar::Markdown, &content)?;
+        Some(Suppressions::new(&content, &tree)?)
+    } else {
+        None
+    };
+
+    // Line breaks inserted by wrapping use the configured line ending, which
+    // also replaces the existing ones unless the file is ignored. Replacing
+    // them keeps the line indices the suppressions refer to.
+    let eol = match config.line_ending {
+        LineEnding::Auto => dominant_line_ending(&content),
+        LineEnding::Lf => "\n",
+        LineEnding::Crlf => "\r\n",
+        LineEnding::Native if cfg!(windows) => "\r\n",
+        LineEnding::Native => "\n",
+    };
+    let content = if config.line_ending != LineEnding::Auto
+        && !suppressions
+            .as_ref()
+            .is_some_and(|suppressions| suppressions.ignores_file())
+    {
+        replace_line_endings(&content, eol)
+    } else {
+        content
+    };
+
+    // Markdown inputs are wrapped only inside paragraph prose so that code
+    // blocks, tables, headings and other blocks are kept intact. Other inputs
+    // retain the plain line-by-line wrapping pass.
+    let wrappable = if markdown {
+        Some(wrappable_ranges(&content)?)
+    } else {
+        None
+    };
+
     let line_breaker = LineBreaker::builder()
         .ambiguous_width(config.ambiguous_width)
         .max_width(config.max_width)
+        .prohibited_start(config.line_break.prohibited_start())
+        .prohibited_end(config.line_break.prohibited_end())
+        .hanging_punctuation(config.line_break.hanging_punctuation)
+        .strategy(config.line_break.strategy)
+        .push_in_tolerance(config.line_break.push_in_tolerance)
+        .inseparable(&config.line_break.inseparable)
         .build()?;
 
     // Iterate over each line in the input content, including line endings
-    for line in content.lines_inclusive() {
-        let mut remainings = line;
+    let mut line_start = 0;
+    for (line_index, line) in content.lines_inclusive().enumerate() {
+        let line_range = line_start..line_start + line.len();
+        line_start = line_range.end;
+        if suppressions
+            .as_ref()
+            .is_some_and(|suppressions| suppressions.is_suppressed(line_index as u32, "W001"))
+        {
+            write!(stdout, "{line}")?;
+            continue;
+        }
+
+        // Lines added by wrapping start with the prefix of the containers
+        // (block quotes and list items) enclosing the paragraph. The line is
+        // never broken before its prose, which would leave a list marker or
+        // a `>` on a line of its own.
+        let (prefix, unbreakable) = match &wrappable {
+            None => ("", Vec::new()),
+            Some(wrappables) => match wrappables.iter().find(|wrappable| {
+                wrappable.range.start < line_range.end && line_range.start < wrappable.range.end
+            }) {
+                Some(wrappable) => {
+                    let prose_start = if line_range.start <= wrappable.range.start {
+                        wrappable.range.start
+                    } else {
+                        wrappable
+                            .continuations
+                            .iter()
+                            .find(|continuation| continuation.start == line_range.start)
+                            .map_or(line_range.start, |continuation| continuation.end)
+                    };
+                    let mut unbreakable = wrappable.unbreakable.clone();
+                    unbreakable.push(line_range.start..prose_start + 1);
+                    (wrappable.prefix.as_str(), unbreakable)
+                }
+                None => {
+                    write!(stdout, "{line}")?;
+                    continue;
+                }
+            },
+        };
+        let indent = line_breaker.width(prefix);
 
         // Iterate over wrap points in the line
+        let mut remainings = line;
+        let mut next_indent = 0;
         while let BreakPoint::WrapPoint {
             overflow_pos,
             adjustment,
-        } = line_breaker.next_line_break(remainings)
-        {
-            // Write the part before the wrap point
+        } = line_breaker.next_line_break_with_unbreakable(
+            remainings,
+            next_indent,
+            &relative_ranges(&unbreakable, line_range.end - remainings.len()),
+        ) {
+            // Write the part before the wrap point without the spaces at its end
             let (before, after) = remainings.split_at(overflow_pos - adjustment);
-            writeln!(stdout, "{before}")?;
+            write!(stdout, "{}{eol}", trim_wrap_point(before))?;
+            write!(stdout, "{prefix}")?;
             remainings = after;
+            next_indent = indent;
         }
 
         // Write any remaining part of the line after the last wrap point
@@ -46,10 +154,224 @@ pub(crate) fn format_one_file<W: std::io::Write>(
     Ok(())
 }
 
+/// Formats the paragraphs touched by the range from `start` to `end` and
+/// returns the edits replacing the changed lines.
+///
+/// Markdown documents are formatted as Markdown, and documents of other
+/// grammars line by line. See [`format_content_range`] for how the range is
+/// expanded.
+pub(crate) fn format_range(
+    config: &Config,
+    document: &Document,
+    start: Position,
+    end: Position,
+) -> anyhow::Result<Vec<TextEdit>> {
+    let markdown = document.grammar == Grammar::Markdown;
+    format_content_range(config, markdown, &document.content, start, end)
+}
+
+/// Formats the paragraphs of `content` touched by the range from `start` to
+/// `end` and returns the edits replacing the changed lines.
+///
+/// An end at the start of a line does not touch that line. See
+/// [`format_content_lines`] for how the range is expanded.
+pub(crate) fn format_content_range(
+    config: &Config,
+    markdown: bool,
+    content: &str,
+    start: Position,
+    end: Position,
+) -> anyhow::Result<Vec<TextEdit>> {
+    let end = if start.line < end.line && end.column == 0 {
+        end.line
+    } else {
+        end.line + 1
+    };
+    let lines = start.line as usize..end as usize;
+    format_content_lines(config, markdown, content, std::slice::from_ref(&lines))
+}
+
+/// Formats the paragraphs of `content` touched by the ranges of line indices
+/// and returns the edits replacing the changed lines.
+///
+/// The ranges are expanded to whole paragraphs, that is runs of lines
+/// separated by blank lines, since wrapping and reflowing depend on the
+/// neighbouring lines. The whole content is formatted so that the enclosing
+/// Markdown containers and the suppression comments are honoured, and only
+/// the edits inside the paragraphs are returned.
+pub(crate) fn format_content_lines(
+    config: &Config,
+    markdown: bool,
+    content: &str,
+    lines: &[Range<usize>],
+) -> anyhow::Result<Vec<TextEdit>> {
+    let mut paragraphs: Vec<Range<usize>> = lines
+        .iter()
+        .map(|lines| paragraph_lines(content, lines.clone()))
+        .collect();
+    paragraphs.sort_by_key(|paragraph| paragraph.start);
+
+    let mut formatted = Vec::new();
+    format_one_file(&mut formatted, config, markdown, content)?;
+    let formatted = String::from_utf8(formatted)?;
+
+    // Formatting keeps the blank lines between paragraphs, so the formatted
+    // text of each paragraph lies between the same blank lines as its source.
+    let old_lines: Vec<&str> = content.lines_inclusive().collect();
+    let new_lines: Vec<&str> = formatted.lines_inclusive().collect();
+    let blank_lines = |lines: &[&str]| -> Vec<usize> {
+        (0..lines.len())
+            .filter(|&index| lines[index].trim().is_empty())
+            .collect()
+    };
+    let (old_blanks, new_blanks) = (blank_lines(&old_lines), blank_lines(&new_lines));
+    if old_blanks.len() != new_blanks.len() {
+        anyhow::bail!("formatting changed the blank lines between paragraphs");
+    }
+    let new_line_index = |index: usize| -> usize {
+        let blanks_before = old_blanks.partition_point(|&blank| blank < index);
+        if index == 0 {
+            0
+        } else if index == old_lines.len() {
+            new_lines.len()
+        } else if old_blanks.get(blanks_before) == Some(&index) {
+            new_blanks[blanks_before]
+        } else {
+            new_blanks[blanks_before - 1] + 1
+        }
+    };
+
+    let mut edits = Vec::new();
+    let mut formatted_end = 0;
+    for paragraph in paragraphs {
+        // Paragraphs touched by several ranges are formatted once.
+        let start = paragraph.start.max(formatted_end);
+        if paragraph.end <= start {
+            continue;
+        }
+        formatted_end = paragraph.end;
+
+        let offset: usize = old_lines[..start].iter().map(|line| line.len()).sum();
+        let old = old_lines[start..paragraph.end].concat();
+        let new = new_lines[new_line_index(start)..new_line_index(paragraph.end)].concat();
+        edits.extend(line_edits(&old, &new).into_iter().map(|edit| TextEdit {
+            range: offset + edit.range.start..offset + edit.range.end,
+            replacement: edit.replacement,
+        }));
+    }
+    Ok(edits)
+}
+
+/// Returns the range of line indices expanded to the paragraphs containing
+/// its first and last lines.
+pub(crate) fn paragraph_lines(content: &str, lines: Range<usize>) -> Range<usize> {
+    let blanks: Vec<bool> = content
+        .lines_inclusive()
+        .map(|line| line.trim().is_empty())
+        .collect();
+    let mut start = lines.start.min(blanks.len());
+    let mut end = lines.end.clamp(start, blanks.len());
+    while 0 < start && start < blanks.len() && !blanks[start] && !blanks[start - 1] {
+        start -= 1;
+    }
+    while start < end && end < blanks.len() && !blanks[end - 1] && !blanks[end] {
+        end += 1;
+    }
+    start..end
+}
+
+/// Returns edits that turn `old` into `new` by replacing whole lines.
+pub(crate) fn line_edits(old: &str, new: &str) -> Vec<TextEdit> {
+    let diff = TextDiff::from_lines(old, new);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+
+    // Byte offset of the start of each old line, followed by the end of text
+    let mut offsets = vec![0];
+    for line in old_lines {
+        offsets.push(offsets[offsets.len() - 1] + line.len());
+    }
+
+    let mut edits = Vec::new();
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        edits.push(TextEdit {
+            range: offsets[old_range.start]..offsets[old_range.end],
+            replacement: new_lines[new_range].concat(),
+        });
+    }
+    edits
+}
+
+/// Removes the spaces at the end of a line broken by wrapping.
+///
+/// The spaces are kept if nothing else is on the line, or if removing them
+/// would leave a backslash at its end, which Markdown reads as a hard line break.
+fn trim_wrap_point(line: &str) -> &str {
+    let trimmed = line.trim_end_matches(' ');
+    let backslashes = trimmed.len() - trimmed.trim_end_matches('\\').len();
+    if trimmed.is_empty() || backslashes % 2 == 1 {
+        line
+    } else {
+        trimmed
+    }
+}
+
+/// Returns the most common line ending in `text`, or LF if it has none.
+///
+/// Ties are resolved in the order of LF, CR+LF, and CR.
+fn dominant_line_ending(text: &str) -> &'static str {
+    let mut counts = [("\n", 0), ("\r\n", 0), ("\r", 0)];
+    for line in text.lines_inclusive() {
+        let index = if line.ends_with("\r\n") {
+            1
+        } else if line.ends_with('\n') {
+            0
+        } else if line.ends_with('\r') {
+            2
+        } else {
+            continue;
+        };
+        counts[index].1 += 1;
+    }
+    counts
+        .iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map_or("\n", |(eol, _)| eol)
+}
+
+/// Replaces every line ending in `text` with `eol`.
+fn replace_line_endings(text: &str, eol: &str) -> String {
+    let mut replaced = String::with_capacity(text.len());
+    for line in text.lines_inclusive() {
+        let content = line.trim_end_matches(['\r', '\n']);
+        replaced.push_str(content);
+        if content.len() < line.len() {
+            replaced.push_str(eol);
+        }
+    }
+    replaced
+}
+
+/// Returns the parts of `ranges` after `offset`, relative to `offset`.
+fn relative_ranges(ranges: &[Range<usize>], offset: usize) -> Vec<Range<usize>> {
+    ranges
+        .iter()
+        .filter(|range| offset < range.end)
+        .map(|range| range.start.saturating_sub(offset)..(range.end - offset))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
+
     use super::*;
-    use crate::config::SpacingRule;
+    use crate::{config::SpacingRule, markdown_spacing::apply_text_edits};
 
     fn config() -> Config {
         let mut config = Config {
@@ -60,9 +382,19 @@ mod tests {
         config
     }
 
-    fn format(apply_markdown_spacing: bool, source: &str) -> String {
+    fn format(markdown: bool, source: &str) -> String {
         let mut output = Vec::new();
-        format_one_file(&mut output, &config(), apply_markdown_spacing, source).unwrap();
+        format_one_file(&mut output, &config(), markdown, source).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn format_with_width(markdown: bool, max_width: u32, source: &str) -> String {
+        let config = Config {
+            max_width,
+            ..Config::default()
+        };
+        let mut output = Vec::new();
+        format_one_file(&mut output, &config, markdown, source).unwrap();
         String::from_utf8(output).unwrap()
     }
 
@@ -76,4 +408,279 @@ mod tests {
         let source = "{\"value\":\"漢A\"}\n";
         assert_eq!(format(false, source), source);
     }
+
+    #[test]
+    fn format_wraps_markdown_paragraph_prose() {
+        assert_eq!(
+            format_with_width(true, 4, "あいうえお\n"),
+            "あい\nうえ\nお\n"
+        );
+    }
+
+    #[test]
+    fn format_keeps_non_paragraph_markdown_blocks_intact() {
+        let source = concat!(
+            "# あいうえお\n",
+            "\n",
+            "あいうえお\n",
+            "==========\n",
+            "\n",
+            "```\n",
+            "あいうえお\n",
+            "```\n",
+            "\n",
+            "    あいうえお\n",
+            "\n",
+            "| あいうえお | b |\n",
+            "|---|---|\n",
+            "\n",
+            "[label]: https://example.test/あいうえお\n",
+            "\n",
+            "<div>\n",
+            "あいうえお\n",
+            "</div>\n",
+        );
+        assert_eq!(format_with_width(true, 4, source), source);
+    }
+
+    #[test]
+    fn format_reflows_markdown_paragraphs_in_reflow_mode() {
+        let config = Config {
+            max_width: 10,
+            wrap: WrapMode::Reflow,
+            ..Config::default()
+        };
+        let source = "あい\nうえおかき\nく\n\nfoo\nbar baz\n";
+        let mut output = Vec::new();
+        format_one_file(&mut output, &config, true, source).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "あいうえお\nかきく\n\nfoo bar\nbaz\n"
+        );
+    }
+
+    #[test]
+    fn format_keeps_short_lines_in_split_mode() {
+        let source = "あい\nうえ\n";
+        assert_eq!(format_with_width(true, 10, source), source);
+    }
+
+    #[test]
+    fn format_indents_wrapped_lines_inside_containers() {
+        let source = concat!(
+            "- あいうえお\n",
+            "\n",
+            "1. あいうえお\n",
+            "\n",
+            "> あいうえお\n",
+            "\n",
+            "> - あいうえお\n",
+        );
+        assert_eq!(
+            format_with_width(true, 8, source),
+            concat!(
+                "- あいう\n",
+                "  えお\n",
+                "\n",
+                "1. あい\n",
+                "   うえ\n",
+                "   お\n",
+                "\n",
+                "> あいう\n",
+                "> えお\n",
+                "\n",
+                "> - あい\n",
+                ">   うえ\n",
+                ">   お\n",
+            )
+        );
+    }
+
+    #[test]
+    fn format_reflows_paragraphs_inside_containers() {
+        let config = Config {
+            max_width: 8,
+            wrap: WrapMode::Reflow,
+            ..Config::default()
+        };
+        let source = "> あい\n> うえ\n> お\n";
+        let mut output = Vec::new();
+        format_one_file(&mut output, &config, true, source).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "> あいう\n> えお\n");
+    }
+
+    #[test]
+    fn format_wraps_every_line_of_non_markdown_input() {
+        assert_eq!(
+            format_with_width(false, 4, "# あいうえお\n"),
+            "# あ\nいう\nえお\n"
+        );
+    }
+
+    #[test]
+    fn format_inserts_the_dominant_line_ending() {
+        assert_eq!(
+            format_with_width(true, 8, "あいうえお\r\nかき\r\n\r\nく\n"),
+            "あいうえ\r\nお\r\nかき\r\n\r\nく\n"
+        );
+        assert_eq!(
+            format_with_width(false, 8, "あいうえお\nかき\r\nく\n"),
+            "あいうえ\nお\nかき\r\nく\n"
+        );
+    }
+
+    #[rstest]
+    #[case(LineEnding::Lf, "あいうえ\nお\nかき\n\nく\n")]
+    #[case(LineEnding::Crlf, "あいうえ\r\nお\r\nかき\r\n\r\nく\r\n")]
+    fn format_replaces_line_endings_as_configured(
+        #[case] line_ending: LineEnding,
+        #[case] expected: &str,
+    ) {
+        let config = Config {
+            max_width: 8,
+            line_ending,
+            ..Config::default()
+        };
+        let mut output = Vec::new();
+        format_one_file(&mut output, &config, true, "あいうえお\r\nかき\n\r\nく\n").unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn format_keeps_line_endings_of_ignored_files() {
+        let config = Config {
+            line_ending: LineEnding::Lf,
+            ..Config::default()
+        };
+        let source = "<!-- cjkfmt-ignore-file -->\r\n\r\nあ\r\n";
+        let mut output = Vec::new();
+        format_one_file(&mut output, &config, true, source).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), source);
+    }
+
+    #[rstest]
+    #[case("foo ", "foo")]
+    #[case("foo  ", "foo")]
+    #[case("   ", "   ")]
+    #[case("foo\\ ", "foo\\ ")]
+    #[case("foo\\\\ ", "foo\\\\")]
+    fn trim_wrap_point_removes_spaces_but_not_hard_breaks(
+        #[case] line: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(trim_wrap_point(line), expected);
+    }
+
+    #[test]
+    fn format_keeps_hard_line_breaks_at_the_end_of_wrapped_lines() {
+        assert_eq!(
+            format_with_width(true, 6, "あいう  \nえお\n"),
+            "あいう  \nえお\n"
+        );
+        assert_eq!(
+            format_with_width(true, 8, "あいうえ\\\nお\n"),
+            "あいう\nえ\\\nお\n"
+        );
+    }
+
+    #[rstest]
+    #[case("a\nb\n\nc\nd\n\ne\n", 3..4, 3..5)]
+    #[case("a\nb\n\nc\nd\n\ne\n", 1..4, 0..5)]
+    #[case("a\nb\n\nc\nd\n\ne\n", 2..3, 2..3)]
+    #[case("a\nb\n\nc\nd\n\ne\n", 6..10, 6..7)]
+    #[case("a\nb\n\nc\nd\n\ne\n", 0..usize::MAX, 0..7)]
+    #[case("a\nb\n", 5..6, 2..2)]
+    fn paragraph_lines_expands_lines_to_paragraphs(
+        #[case] content: &str,
+        #[case] lines: Range<usize>,
+        #[case] expected: Range<usize>,
+    ) {
+        assert_eq!(paragraph_lines(content, lines), expected);
+    }
+
+    #[test]
+    fn line_edits_replace_only_changed_lines() {
+        let edits = line_edits("a\nb\nc\n", "a\nB\nc\nd\n");
+        assert_eq!(
+            edits,
+            [
+                TextEdit {
+                    range: 2..4,
+                    replacement: "B\n".to_string()
+                },
+                TextEdit {
+                    range: 6..6,
+                    replacement: "d\n".to_string()
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(Position::new(3, 1), Position::new(3, 2))]
+    #[case(Position::new(2, 0), Position::new(4, 0))]
+    #[case(Position::new(1, 0), Position::new(2, 1))]
+    fn format_range_formats_only_the_touched_paragraphs(
+        #[case] start: Position,
+        #[case] end: Position,
+    ) {
+        let config = Config {
+            max_width: 4,
+            ..Config::default()
+        };
+        let document = Document::new(
+            "あいうえお\n\nかきくけこ\nさ\n\nたちつてと\n",
+            Grammar::Markdown,
+            None::<String>,
+        );
+
+        let edits = format_range(&config, &document, start, end).unwrap();
+        assert_eq!(
+            edits,
+            [TextEdit {
+                range: 17..33,
+                replacement: "かき\nくけ\nこ\n".to_string()
+            }]
+        );
+    }
+
+    #[rstest]
+    #[case(3..4, 12..22)]
+    #[case(2..3, 10..12)]
+    #[case(0..1, 0..10)]
+    fn format_content_lines_keeps_bytes_outside_the_paragraphs(
+        #[case] lines: Range<usize>,
+        #[case] paragraph: Range<usize>,
+    ) {
+        let config = Config {
+            line_ending: LineEnding::Lf,
+            ..Config::default()
+        };
+        let content = "abc\r\ndef\r\n\r\nghi\r\njkl\r\n\r\nmno\r\n";
+
+        let edits = format_content_lines(&config, true, content, &[lines]).unwrap();
+        let formatted = apply_text_edits(content, edits).unwrap();
+        let changed = paragraph.start..formatted.len() - (content.len() - paragraph.end);
+        assert_eq!(formatted[..changed.start], content[..paragraph.start]);
+        assert_eq!(formatted[changed.end..], content[paragraph.end..]);
+        assert_eq!(formatted[changed], content[paragraph].replace("\r\n", "\n"));
+    }
+
+    #[test]
+    fn format_range_does_not_touch_a_line_the_range_ends_at() {
+        let config = Config {
+            max_width: 4,
+            ..Config::default()
+        };
+        let document = Document::new(
+            "あいうえお\n\nかきくけこ\n",
+            Grammar::Markdown,
+            None::<String>,
+        );
+
+        let edits = format_range(&config, &document, Position::new(0, 0), Position::new(2, 0));
+        assert_eq!(edits.unwrap().len(), 1);
+        let edits = format_range(&config, &document, Position::new(1, 0), Position::new(2, 0));
+        assert_eq!(edits.unwrap(), []);
+    }
 }
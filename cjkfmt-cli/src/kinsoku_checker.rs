@@ -0,0 +1,139 @@
+use cjkfmt_core::{diagnostic::Diagnostic, lines_inclusive::LinesInclusiveExt, position::Position};
+use cjkfmt_parser::NodeVisitor;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    config::Config,
+    document::Document,
+    markdown_wrap::continuation_ranges,
+    spacing::{is_cjk, is_wide},
+};
+
+/// Checks for line breaks in paragraphs that violate the kinsoku rule.
+///
+/// A line of a paragraph must not start with a character prohibited at the
+/// start of a line, nor end with a character prohibited at the end of a line.
+/// Only line breaks next to CJK or full-width characters are checked, as
+/// line breaks in Latin text are rendered as spaces.
+#[derive(Debug)]
+pub struct KinsokuChecker<'a> {
+    config: &'a Config,
+    document: &'a Document,
+    prohibited_start: Vec<String>,
+    prohibited_end: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> KinsokuChecker<'a> {
+    /// Creates a new KinsokuChecker for the given config and document.
+    pub fn new(config: &'a Config, document: &'a Document) -> Self {
+        let graphemes = |text: String| text.graphemes(true).map(str::to_owned).collect();
+        Self {
+            config,
+            document,
+            prohibited_start: graphemes(config.line_break.prohibited_start()),
+            prohibited_end: graphemes(config.line_break.prohibited_end()),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Returns a slice of collected diagnostics.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+}
+
+/// Implements the NodeVisitor trait to check the line breaks of each paragraph.
+impl<'a> NodeVisitor for KinsokuChecker<'a> {
+    fn on_enter(&mut self, node: &tree_sitter::Node) {
+        if node.kind() != "inline"
+            || node
+                .parent()
+                .is_none_or(|parent| parent.kind() != "paragraph")
+        {
+            return;
+        }
+
+        let range = node.byte_range();
+        let text = &self.document.content[range.clone()];
+        let continuations = continuation_ranges(*node);
+        let mut line_start = 0;
+        for line in text.lines_inclusive() {
+            let line_end = line_start + line.len();
+            let content = line.trim_end_matches(['\r', '\n']);
+            if content.len() == line.len() {
+                break;
+            }
+
+            // The last character before the break, ignoring a hard line break marker
+            let body = content.trim_end_matches([' ', '\t']);
+            let backslashes = body.len() - body.trim_end_matches('\\').len();
+            let body = if backslashes % 2 == 1 {
+                &body[..body.len() - 1]
+            } else {
+                body
+            };
+            let last = body.graphemes(true).next_back();
+
+            // The first character after the break, skipping the container prefix
+            let prefix_end = continuations
+                .iter()
+                .find(|continuation| continuation.start == line_end)
+                .map_or(line_end, |continuation| continuation.end);
+            let following = &text[prefix_end..];
+            let next_line = following.trim_start_matches([' ', '\t']);
+            let first = next_line.graphemes(true).next();
+
+            if ![last, first]
+                .into_iter()
+                .flatten()
+                .any(|g| self.is_cjk_like(g))
+            {
+                line_start = line_end;
+                continue;
+            }
+
+            if let Some(last) = last
+                && self.prohibited_end.iter().any(|g| g == last)
+            {
+                let start = range.start + line_start + body.len() - last.len();
+                let message = format!("Line ends with \"{last}\", which cannot end a line");
+                self.push_diagnostic(start, last, "W004", message);
+            }
+
+            if let Some(first) = first
+                && self.prohibited_start.iter().any(|g| g == first)
+            {
+                let start = range.start + prefix_end + (following.len() - next_line.len());
+                let message = format!("Line starts with \"{first}\", which cannot start a line");
+                self.push_diagnostic(start, first, "W003", message);
+            }
+
+            line_start = line_end;
+        }
+    }
+
+    /// Called when exiting a node in the parse tree. No action needed here.
+    fn on_exit(&mut self, _node: &tree_sitter::Node) {}
+}
+
+impl<'a> KinsokuChecker<'a> {
+    /// Returns whether a grapheme is a CJK or full-width character.
+    fn is_cjk_like(&self, grapheme: &str) -> bool {
+        grapheme
+            .chars()
+            .next()
+            .is_some_and(|c| is_cjk(self.config, c) || is_wide(self.config, c))
+    }
+
+    fn push_diagnostic(&mut self, start: usize, grapheme: &str, code: &str, message: String) {
+        let content = &self.document.content;
+        self.diagnostics.push(Diagnostic::new(
+            self.document.filename.as_deref(),
+            Position::from_offset(content, start),
+            Position::from_offset(content, start + grapheme.len()),
+            code.to_string(),
+            message,
+        ));
+    }
+}
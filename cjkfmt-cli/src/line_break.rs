@@ -1,9 +1,14 @@
 //! Define [`LineBreaker`] that finds a line break with adherence to kinsoku rule.
-use unicode_linebreak::{BreakClass, break_property};
+use std::ops::Range;
+
+use unicode_linebreak::{BreakClass, break_property, linebreaks};
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
-use crate::{_log::test_log, config::AmbiguousWidth};
+use crate::{
+    _log::test_log,
+    config::{AmbiguousWidth, KinsokuStrategy},
+};
 
 /// Grapheme clusters prohibited at the start of a line.
 pub const PROHIBITED_START: &str = ")]｝〕〉》」』】〙〗〟'\"｠»\
@@ -16,6 +21,57 @@ pub const PROHIBITED_START: &str = ")]｝〕〉》」』】〙〗〟'\"｠»\
 /// Grapheme clusters prohibited at the end of a line.
 pub const PROHIBITED_END: &str = "([｛〔〈《「『【〘〖〝'\"｟«";
 
+/// Grapheme clusters allowed to hang past the maximum width (burasage).
+pub const HANGING_PUNCTUATION: &str = "、。，．";
+
+/// Grapheme clusters prohibited at the start of a line by the strict rule of JIS X 4051.
+///
+/// These are closing brackets, hyphens, dividing punctuation marks, middle
+/// dots, full stops, commas, iteration marks, the prolonged sound mark and
+/// small kana.
+pub const PROHIBITED_START_STRICT: &str = ")）]］}｝〕〉》」』】〙〗〟’”｠»'\"\
+    ‐゠–〜\
+    ？！?!‼⁇⁈⁉\
+    ・：；:;\
+    。．.\
+    、，,\
+    ヽヾゝゞ々〻\
+    ー\
+    ァィゥェォッャュョヮヵヶぁぃぅぇぉっゃゅょゎゕゖㇰㇱㇲㇳㇴㇵㇶㇷㇸㇹㇺㇻㇼㇽㇾㇿ";
+
+/// Grapheme clusters prohibited at the start of a line by the loose rule of JIS X 4051.
+///
+/// Unlike the strict rule, small kana, the prolonged sound mark, iteration
+/// marks and hyphens may start a line.
+pub const PROHIBITED_START_LOOSE: &str = ")）]］}｝〕〉》」』】〙〗〟’”｠»'\"\
+    ？！?!‼⁇⁈⁉\
+    ・：；:;\
+    。．.\
+    、，,";
+
+/// Grapheme clusters prohibited at the end of a line by JIS X 4051.
+///
+/// These are the opening brackets, which are the same in the strict and the loose rules.
+pub const PROHIBITED_END_JIS_X_4051: &str = "(（[［{｛〔〈《「『【〘〖〝‘“｟«'\"";
+
+/// Grapheme clusters which cannot be separated from their repetition, such as "……".
+pub const INSEPARABLE_REPEATS: &str = "…‥―—〜～";
+
+/// Separators which cannot be separated from the digits around them, such as "1,000".
+const NUMBER_SEPARATORS: &str = ",.，．";
+
+/// Currency signs which cannot be separated from the number after them, such as "¥1,000".
+const CURRENCY_SIGNS: &str = "$¢£¥€₩￥＄￠￡￦";
+
+/// Units which cannot be separated from the number before them, such as "100 km".
+///
+/// Single-letter units such as "m" and "s" are left out, as they are also
+/// ordinary words and symbols in prose after a number.
+const UNITS: &[&str] = &[
+    "%", "％", "‰", "°", "℃", "℉", "′", "″", "円", "km", "cm", "mm", "kg", "mg", "mL", "ms", "min",
+    "GHz", "MHz", "kHz", "Hz", "TB", "GB", "MB", "KB", "kB", "px", "pt",
+];
+
 /// A line break point detected by [`LineBreaker`].
 #[derive(Debug, PartialEq, Eq)]
 pub enum BreakPoint {
@@ -29,6 +85,8 @@ pub enum BreakPoint {
     ///
     /// - `overflow_pos`: The position of the character that caused the overflow.
     ///   This is the byte index of the character in the original line.
+    ///   Characters pushed in to the line (oikomi) or hanging past the
+    ///   maximum width do not cause an overflow, so it points after them.
     /// - `adjustment`: The number of bytes to backtrack to find an acceptable break point.
     WrapPoint {
         overflow_pos: usize,
@@ -48,6 +106,10 @@ pub struct LineBreaker {
     max_width: u32,
     prohibited_start: Vec<String>,
     prohibited_end: Vec<String>,
+    hanging_punctuation: bool,
+    strategy: KinsokuStrategy,
+    push_in_tolerance: u32,
+    inseparable: Vec<String>,
 }
 
 /// Build a [`LineBreaker`].
@@ -77,7 +139,7 @@ impl LineBreakerBuilder {
     /// Sets the grapheme clusters that are prohibited at the start of a line.
     ///
     /// This method replaces the default set of prohibited grapheme clusters.
-    fn prohibited_start<S: AsRef<str>>(mut self, graphemes: S) -> Self {
+    pub fn prohibited_start<S: AsRef<str>>(mut self, graphemes: S) -> Self {
         self.line_breaker.prohibited_start = graphemes
             .as_ref()
             .graphemes(true)
@@ -89,7 +151,7 @@ impl LineBreakerBuilder {
     /// Sets the grapheme clusters that are prohibited at the end of a line.
     ///
     /// This method replaces the default set of prohibited grapheme clusters.
-    fn prohibited_end<S: AsRef<str>>(mut self, graphemes: S) -> Self {
+    pub fn prohibited_end<S: AsRef<str>>(mut self, graphemes: S) -> Self {
         self.line_breaker.prohibited_end = graphemes
             .as_ref()
             .graphemes(true)
@@ -98,6 +160,43 @@ impl LineBreakerBuilder {
         self
     }
 
+    /// Sets whether a full stop or a comma may hang past the maximum width.
+    ///
+    /// When enabled, a punctuation mark in [`HANGING_PUNCTUATION`] that would
+    /// overflow a line stays on it instead of pushing the preceding characters
+    /// to the next line (burasage). At most one mark hangs on each line.
+    pub fn hanging_punctuation(mut self, hanging_punctuation: bool) -> Self {
+        self.line_breaker.hanging_punctuation = hanging_punctuation;
+        self
+    }
+
+    /// Sets how to resolve a line break prohibited by the kinsoku rule.
+    pub fn strategy(mut self, strategy: KinsokuStrategy) -> Self {
+        self.line_breaker.strategy = strategy;
+        self
+    }
+
+    /// Sets how far characters pushed in to a line may exceed the maximum width.
+    ///
+    /// The width is measured in the same unit as [`Self::max_width`].
+    pub fn push_in_tolerance(mut self, push_in_tolerance: u32) -> Self {
+        self.line_breaker.push_in_tolerance = push_in_tolerance;
+        self
+    }
+
+    /// Sets additional sequences which must not be split across lines.
+    ///
+    /// These are kept together in addition to the built-in sequences such as
+    /// "……" and "100 km".
+    pub fn inseparable<I, S>(mut self, sequences: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.line_breaker.inseparable = sequences.into_iter().map(Into::into).collect();
+        self
+    }
+
     /// Finish building and returns a [`LineBreaker`].
     pub fn build(self) -> anyhow::Result<LineBreaker> {
         if self.line_breaker.max_width < 2 {
@@ -120,6 +219,10 @@ impl LineBreaker {
                 max_width: 80,
                 prohibited_start: Vec::new(),
                 prohibited_end: Vec::new(),
+                hanging_punctuation: false,
+                strategy: KinsokuStrategy::PushOut,
+                push_in_tolerance: 2,
+                inseparable: Vec::new(),
             },
         }
         .prohibited_start(PROHIBITED_START)
@@ -132,6 +235,19 @@ impl LineBreaker {
         self.max_width
     }
 
+    /// Returns the rendered width of the given text.
+    ///
+    /// The width is measured in terms of fullwidth characters.
+    /// For example, the width of "あ" is 2, and the width of "a" is 1.
+    pub fn width(&self, text: &str) -> u32 {
+        let width = if self.ambiguous_width == AmbiguousWidth::Narrow {
+            text.width()
+        } else {
+            text.width_cjk()
+        };
+        width as u32
+    }
+
     // TODO: Remove this method
     fn prohibited_start<'a>(&'a self) -> Vec<&'a str> {
         self.prohibited_start
@@ -148,12 +264,39 @@ impl LineBreaker {
     }
 
     /// Finds a line break in the given line and returns its byte index.
-    #[allow(clippy::collapsible_if)]
     pub fn next_line_break(&self, line: &str) -> BreakPoint {
-        test_log!("next_line_break() {:?}", line);
+        self.next_line_break_with_indent(line, 0)
+    }
+
+    /// Finds a line break in the given line which is rendered after an indent.
+    ///
+    /// `indent` is the width of a prefix written before `line`, such as the
+    /// `> ` of a block quote. It counts against the maximum width of the line.
+    pub fn next_line_break_with_indent(&self, line: &str, indent: u32) -> BreakPoint {
+        self.next_line_break_with_unbreakable(line, indent, &[])
+    }
+
+    /// Finds a line break in the given line, which is rendered after an indent,
+    /// outside of the given byte ranges.
+    ///
+    /// `unbreakable` are byte ranges of `line` which must not be broken inside,
+    /// such as code spans and URLs. They are moved to the next line as a whole,
+    /// or overflow the line if they do not fit in a line by themselves.
+    #[allow(clippy::collapsible_if)]
+    pub fn next_line_break_with_unbreakable(
+        &self,
+        line: &str,
+        indent: u32,
+        unbreakable: &[Range<usize>],
+    ) -> BreakPoint {
+        test_log!(
+            "next_line_break_with_unbreakable() {:?} {indent} {unbreakable:?}",
+            line
+        );
 
         let mut graphemes: Vec<&str> = Vec::with_capacity(128);
-        let mut acc_width = 0u32;
+        let mut acc_width = indent;
+        let mut push_in_end = 0;
         for (i, grapheme) in line.grapheme_indices(true) {
             // Stop if reached EOL.
             if grapheme == "\r" || grapheme == "\n" {
@@ -165,21 +308,55 @@ impl LineBreaker {
             }
 
             // Test whether rendering this grapheme cluster will exceed the limit or not
-            let width = if self.ambiguous_width == AmbiguousWidth::Narrow {
-                grapheme.width()
-            } else {
-                grapheme.width_cjk()
-            } as u32;
-            if self.max_width < acc_width + width {
+            let width = self.width(grapheme);
+            // At least one grapheme cluster must stay on a line, even if the
+            // indent alone already fills it.
+            if push_in_end <= i && !graphemes.is_empty() && self.max_width < acc_width + width {
+                // Spaces at a wrap point are removed, so they do not overflow
+                // the line; the next grapheme will break the line after them.
+                if grapheme == " " {
+                    test_log!("  {i:02} {:?} # trailing space", grapheme);
+                    graphemes.push(grapheme);
+                    acc_width += width;
+                    continue;
+                }
+
+                // Let the first overflowing grapheme hang if it is a full stop
+                // or a comma; the next one will break the line after it.
+                if self.hanging_punctuation
+                    && acc_width <= self.max_width
+                    && HANGING_PUNCTUATION.contains(grapheme)
+                {
+                    test_log!("  {i:02} {:?} # hanging punctuation", grapheme);
+                    graphemes.push(grapheme);
+                    acc_width += width;
+                    continue;
+                }
+
                 test_log!(
                     "  {i:02} {:?} # max_width < acc_width + width ({} < {})",
                     grapheme,
                     self.max_width,
                     acc_width + width
                 );
-                if let Some(nbytes_seek_back) =
-                    self.num_bytes_to_seek_back(graphemes.as_slice(), grapheme)
+                let push_out = self.num_bytes_to_seek_back(line, i, unbreakable);
+                if push_out != Some(0)
+                    && let Some((push_in_len, push_in_width)) =
+                        self.push_in(line, i, acc_width, unbreakable)
+                    && self.prefers_push_in(
+                        line,
+                        acc_width,
+                        push_out.map(|nbytes| (i - nbytes, i)),
+                        (i + push_in_len, push_in_width),
+                    )
                 {
+                    test_log!("  {i:02} {:?} # push in {push_in_len} bytes", grapheme);
+                    push_in_end = i + push_in_len;
+                    graphemes.push(grapheme);
+                    acc_width += width;
+                    continue;
+                }
+                if let Some(nbytes_seek_back) = push_out {
                     test_log!(
                         "  split at {:02} --> {:?}",
                         i - nbytes_seek_back,
@@ -204,40 +381,154 @@ impl LineBreaker {
         BreakPoint::EndOfText(line.len())
     }
 
+    /// Finds the grapheme clusters from `pos` in `line` that can be pushed in
+    /// to the current line (oikomi), whose width is `acc_width`.
+    ///
+    /// Returns the number of bytes of and the width of the line with them, or
+    /// `None` if the strategy does not push in, they exceed the tolerance, or
+    /// the line cannot be broken after them.
+    fn push_in(
+        &self,
+        line: &str,
+        pos: usize,
+        acc_width: u32,
+        unbreakable: &[Range<usize>],
+    ) -> Option<(usize, u32)> {
+        if self.strategy == KinsokuStrategy::PushOut {
+            return None;
+        }
+
+        let mut len = 0;
+        let mut width = acc_width;
+        let mut pushed_in = false;
+        for grapheme in line[pos..].graphemes(true) {
+            if !self.prohibited_start.iter().any(|g| g == grapheme) {
+                break;
+            }
+            width += self.width(grapheme);
+            if self.max_width + self.push_in_tolerance < width {
+                return None;
+            }
+            len += grapheme.len();
+            pushed_in = true;
+        }
+
+        if !pushed_in {
+            return None;
+        }
+        let end = pos + len;
+        match line[end..].graphemes(true).next() {
+            None | Some("\r" | "\n" | "\r\n") => Some((len, width)),
+            Some(_) if self.num_bytes_to_seek_back(line, end, unbreakable) == Some(0) => {
+                Some((len, width))
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// Decides whether to push in rather than to push out.
+    ///
+    /// `push_out` is the byte range moved to the next line by pushing out, or
+    /// `None` if the line cannot be broken by pushing out. `push_in` is the
+    /// end of the line and its width after pushing in.
+    ///
+    /// With [`KinsokuStrategy::MinRaggedness`], the choice is the one whose
+    /// line and the next one deviate less from the maximum width, measured
+    /// as the sum of the squared deviations. Lines are not longer than the
+    /// maximum width after the next, and nothing left is no line at all.
+    fn prefers_push_in(
+        &self,
+        line: &str,
+        acc_width: u32,
+        push_out: Option<(usize, usize)>,
+        push_in: (usize, u32),
+    ) -> bool {
+        let Some((push_out_start, push_out_end)) = push_out else {
+            return true;
+        };
+        if self.strategy != KinsokuStrategy::MinRaggedness {
+            return true;
+        }
+
+        let max_width = i64::from(self.max_width);
+        let raggedness = |width: u32, rest: &str| {
+            let rest = rest.split(['\r', '\n']).next().unwrap_or_default();
+            let deviation = max_width - i64::from(width);
+            let next_deviation = if rest.is_empty() {
+                0
+            } else {
+                max_width - i64::from(self.width(rest).min(self.max_width))
+            };
+            deviation * deviation + next_deviation * next_deviation
+        };
+        let push_out_width = acc_width - self.width(&line[push_out_start..push_out_end]);
+        let (push_in_end, push_in_width) = push_in;
+        raggedness(push_in_width, &line[push_in_end..])
+            < raggedness(push_out_width, &line[push_out_start..])
+    }
+
+    /// Finds how many bytes to seek back from `pos` in `line` to reach a
+    /// position where the line can be broken, outside of the `unbreakable` ranges.
     fn num_bytes_to_seek_back(
         &self,
-        preceding_graphemes: &[&str],
-        following_grapheme: &str,
+        line: &str,
+        pos: usize,
+        unbreakable: &[Range<usize>],
     ) -> Option<usize> {
-        debug_assert!(!preceding_graphemes.is_empty());
+        debug_assert!(0 < pos);
+
+        let opportunities = break_opportunities(line);
+        let mut following = line[pos..]
+            .graphemes(true)
+            .next()
+            .expect("`pos` must not be the end of `line`");
+        for (start, grapheme) in line[..pos].grapheme_indices(true).rev() {
+            let break_pos = start + grapheme.len();
+            let nbytes_to_rewind = pos - break_pos;
 
-        let mut nbytes_to_rewind = 0;
-        let mut following = following_grapheme;
-        for grapheme in preceding_graphemes.iter().rev() {
             // Seek back if not breakable according to UAX#14
-            if !is_breakable(grapheme, following) {
-                let grapheme_len = grapheme.len();
-                nbytes_to_rewind += grapheme_len;
-                test_log!("    rewind {grapheme_len}: {grapheme:?} {following:?} (not breakable)",);
+            if opportunities.binary_search(&break_pos).is_err() {
+                test_log!(
+                    "    rewind {nbytes_to_rewind}: {grapheme:?} {following:?} (not breakable)",
+                );
                 following = grapheme;
                 continue;
             }
 
             // Seek back if the preceding character is prohibited at line end
-            if self.prohibited_end().contains(grapheme) {
-                let grapheme_len = grapheme.len();
-                nbytes_to_rewind += grapheme_len;
-                test_log!("    rewind {grapheme_len}: {grapheme:?} {following:?} (prohibited_end)");
+            if self.prohibited_end().contains(&grapheme) {
+                test_log!(
+                    "    rewind {nbytes_to_rewind}: {grapheme:?} {following:?} (prohibited_end)"
+                );
                 following = grapheme;
                 continue;
             }
 
             // Seek back if the following character is prohibited at line start
             if self.prohibited_start().contains(&following) {
-                let grapheme_len = grapheme.len();
-                nbytes_to_rewind += grapheme_len;
                 test_log!(
-                    "    rewind {grapheme_len}: {grapheme:?} {following:?} (prohibited_start)"
+                    "    rewind {nbytes_to_rewind}: {grapheme:?} {following:?} (prohibited_start)"
+                );
+                following = grapheme;
+                continue;
+            }
+
+            // Seek back if the break is inside a range which must not be broken
+            if unbreakable
+                .iter()
+                .any(|range| range.start < break_pos && break_pos < range.end)
+            {
+                test_log!(
+                    "    rewind {nbytes_to_rewind}: {grapheme:?} {following:?} (unbreakable)"
+                );
+                following = grapheme;
+                continue;
+            }
+
+            // Seek back if the break splits a sequence which must be kept together
+            if self.is_inseparable(&line[..break_pos], &line[break_pos..]) {
+                test_log!(
+                    "    rewind {nbytes_to_rewind}: {grapheme:?} {following:?} (inseparable)"
                 );
                 following = grapheme;
                 continue;
@@ -249,41 +540,145 @@ impl LineBreaker {
         test_log!("  cannot rewind anymore");
         None
     }
+
+    /// Returns whether breaking a line between `preceding` and `following`
+    /// splits a sequence which must be kept together (bunri kinshi).
+    ///
+    /// The following sequences cannot be split:
+    ///
+    /// - A grapheme cluster in [`INSEPARABLE_REPEATS`] and its repetition, such as "……"
+    /// - Digits and the separators of a number, such as "1,000"
+    /// - A currency sign and the number after it, such as "¥1,000"
+    /// - A number and the unit after it, optionally with a space between, such as "100 km"
+    /// - Sequences given by [`LineBreakerBuilder::inseparable`]
+    fn is_inseparable(&self, preceding: &str, following: &str) -> bool {
+        let mut before = preceding.chars().rev();
+        let mut after = following.chars();
+        let (Some(last), Some(first)) = (before.next(), after.next()) else {
+            return false;
+        };
+        let second_last = before.next();
+        let second = after.next();
+
+        if last == first && INSEPARABLE_REPEATS.contains(last) {
+            return true;
+        }
+
+        // Digit groups such as "1,000" and "3.14"
+        if is_digit(last) && NUMBER_SEPARATORS.contains(first) && second.is_some_and(is_digit)
+            || second_last.is_some_and(is_digit)
+                && NUMBER_SEPARATORS.contains(last)
+                && is_digit(first)
+            || is_digit(last) && is_digit(first)
+        {
+            return true;
+        }
+
+        if CURRENCY_SIGNS.contains(last) && is_digit(first) {
+            return true;
+        }
+
+        // Units after a number, with or without a space between them
+        let number = preceding.strip_suffix(' ').unwrap_or(preceding);
+        if number.chars().next_back().is_some_and(is_digit)
+            && UNITS.iter().any(|unit| {
+                following
+                    .strip_prefix(unit)
+                    .is_some_and(|rest| !rest.chars().next().is_some_and(char::is_alphanumeric))
+            })
+        {
+            return true;
+        }
+
+        self.inseparable.iter().any(|sequence| {
+            sequence.char_indices().skip(1).any(|(index, _)| {
+                preceding.ends_with(&sequence[..index]) && following.starts_with(&sequence[index..])
+            })
+        })
+    }
 }
 
-/// Check whether a line break is allowed between the given grapheme clusters.
-///
-/// This function is based on UAX#14 so kinsoku rules are not considered.
-#[allow(clippy::let_and_return)]
-fn is_breakable(preceding: &str, following: &str) -> bool {
-    debug_assert!(!preceding.is_empty());
-    debug_assert!(!following.is_empty());
+/// Returns whether the character is a half-width or full-width decimal digit.
+fn is_digit(c: char) -> bool {
+    c.is_ascii_digit() || ('０'..='９').contains(&c)
+}
 
-    let preceding_break_property = preceding
-        .chars()
-        .last()
-        .map(|c| break_property(c as u32))
-        .expect("`preceding` must be non-empty");
-    let following_break_property = following
+/// Returns the byte indices where the Unicode line breaking algorithm (UAX #14) allows breaking `text`.
+///
+/// Kinsoku rules are not considered. Breaks at the end of the text, which the
+/// algorithm always reports, are included.
+///
+/// Conditional Japanese starters such as small kana are resolved to ideographs
+/// as UAX #14 allows, so that the kinsoku sets decide whether they can start a line.
+/// For the same reason, a line may be broken before inseparable characters
+/// such as `…` following an ideograph, as JIS X 4051 allows.
+fn break_opportunities(text: &str) -> Vec<usize> {
+    // Every conditional Japanese starter is encoded in three bytes, as is "あ".
+    let tailored: String = text
         .chars()
-        .next()
-        .map(|c| break_property(c as u32))
-        .expect("`following` must be non-empty");
-    let breakable = match (preceding_break_property, following_break_property) {
-        (BreakClass::After, _) => false,
-        (_, BreakClass::Before) => false,
-        (BreakClass::BeforeAndAfter, _) => false,
-        (_, BreakClass::BeforeAndAfter) => false,
-        (BreakClass::Alphabetic, BreakClass::Alphabetic) => false,
-        (_, BreakClass::Space) => false,
-        (_, _) => true,
-    };
-    // log!(
-    //     "    {preceding:?}({:?}) {following:?}({:?}) --> {breakable}",
-    //     preceding_break_property,
-    //     following_break_property
-    // );
-    breakable
+        .map(|c| match break_property(c as u32) {
+            BreakClass::ConditionalJapaneseStarter => 'あ',
+            _ => c,
+        })
+        .collect();
+    debug_assert_eq!(tailored.len(), text.len());
+    let mut opportunities: Vec<usize> = linebreaks(&tailored).map(|(index, _)| index).collect();
+
+    // The class of the last character, looking through combining marks (LB9
+    // and LB10), with the ambiguous ones resolved to alphabetic (LB1).
+    let mut base = None;
+    let mut after_joiner = false;
+    for (index, c) in tailored.char_indices() {
+        let class = match break_property(c as u32) {
+            BreakClass::Ambiguous | BreakClass::ComplexContext | BreakClass::Unknown => {
+                BreakClass::Alphabetic
+            }
+            class => class,
+        };
+        let breakable = match (base, class) {
+            // `unicode_linebreak` lacks the East Asian exception of LB30.
+            (
+                Some(BreakClass::Alphabetic | BreakClass::HebrewLetter | BreakClass::Numeric),
+                BreakClass::OpenPunctuation,
+            ) => is_east_asian(c),
+            (Some(BreakClass::Ideographic), BreakClass::Inseparable) => true,
+            _ => false,
+        };
+        // Nothing is broken after a zero width joiner (LB8a).
+        if breakable
+            && !after_joiner
+            && let Err(position) = opportunities.binary_search(&index)
+        {
+            opportunities.insert(position, index);
+        }
+        after_joiner = class == BreakClass::ZeroWidthJoiner;
+
+        base = match class {
+            BreakClass::CombiningMark | BreakClass::ZeroWidthJoiner => match base {
+                None
+                | Some(
+                    BreakClass::Mandatory
+                    | BreakClass::CarriageReturn
+                    | BreakClass::LineFeed
+                    | BreakClass::NextLine
+                    | BreakClass::Space
+                    | BreakClass::ZeroWidthSpace,
+                ) => Some(BreakClass::Alphabetic),
+                base => base,
+            },
+            class => Some(class),
+        };
+    }
+    opportunities
+}
+
+/// Returns whether the East Asian Width of the character is F, W, or H.
+fn is_east_asian(c: char) -> bool {
+    use unicode_width::UnicodeWidthChar;
+
+    c.width() == Some(2)
+        || ('\u{FF61}'..='\u{FFDC}').contains(&c)
+        || ('\u{FFE8}'..='\u{FFEE}').contains(&c)
 }
 
 #[cfg(test)]
@@ -293,17 +688,101 @@ mod test {
 
     #[rstest]
     // not breakable between alphabetic characters
-    #[case("a", "a", false)]
-    #[case("a", "あ", true)]
+    #[case("aa", &[2])]
+    #[case("aあ", &[1, 4])]
     // not breakable before a space
-    #[case("a", " ", false)]
-    #[case(" ", "a", true)]
-    fn is_breakable_normal(
-        #[case] preceding: &str,
-        #[case] following: &str,
-        #[case] expected: bool,
-    ) {
-        assert_eq!(is_breakable(preceding, following), expected);
+    #[case("a ", &[2])]
+    #[case(" a", &[1, 2])]
+    // not breakable inside numbers and before their postfixes
+    #[case("3.14", &[4])]
+    #[case("50%", &[3])]
+    // breakable before small kana, which are left to the kinsoku rule
+    #[case("あぁ", &[3, 6])]
+    // breakable only after slashes in URLs
+    #[case("https://example.com/path", &[8, 20, 24])]
+    // not breakable after opening quotes
+    #[case("\"a\" b", &[4, 5])]
+    fn break_opportunities_normal(#[case] text: &str, #[case] expected: &[usize]) {
+        assert_eq!(break_opportunities(text), expected);
+    }
+
+    /// Cases of the Unicode `LineBreakTest.txt` skipped on purpose, by line number.
+    const SKIPPED_LINE_BREAK_TESTS: &[(&str, &[usize])] = &[
+        (
+            // The test data follows Example 7 of UAX #14 Section 8.2.
+            "tailoring of numbers not implemented: `)%`, `$(`, and `a.2` are kept together",
+            &[
+                1161, 1163, 1165, 1167, 2873, 2875, 4425, 4427, 4473, 4475, 4597, 4599, 4645, 4647,
+                5109, 5111, 6149, 6151, 6153, 6155, 7477, 7486, 7491, 7576, 7577, 7578, 7579, 7580,
+                7581, 7583, 7584, 7585, 7586, 7587,
+            ],
+        ),
+        (
+            "tailored to break before inseparable characters after ideographs",
+            &[2497, 2499, 7650],
+        ),
+        (
+            "unassigned pictographic code points are not Extended_Pictographic (LB30b)",
+            &[7681],
+        ),
+    ];
+
+    /// Checks [`break_opportunities`] against the Unicode `LineBreakTest.txt`.
+    ///
+    /// Besides [`SKIPPED_LINE_BREAK_TESTS`], cases with conditional Japanese
+    /// starters are skipped, as they are tailored to be ideographs.
+    #[test]
+    fn uax14_line_break_test() {
+        let path = format!(
+            "{}/test_cases/uax14/LineBreakTest.txt",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let data = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{path}: {e}"));
+        let mut num_cases = 0;
+        for (line_index, line) in data.lines().enumerate() {
+            let case = line.split('#').next().unwrap_or_default().trim();
+            if case.is_empty() {
+                continue;
+            }
+
+            let mut text = String::new();
+            let mut expected = Vec::new();
+            for token in case.split_whitespace() {
+                match token {
+                    "÷" if !text.is_empty() => expected.push(text.len()),
+                    "÷" | "×" => {}
+                    _ => text.push(
+                        u32::from_str_radix(token, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .unwrap_or_else(|| panic!("{path}:{}: {token:?}", line_index + 1)),
+                    ),
+                }
+            }
+            if text
+                .chars()
+                .any(|c| break_property(c as u32) == BreakClass::ConditionalJapaneseStarter)
+            {
+                continue;
+            }
+
+            let actual = break_opportunities(&text);
+            if let Some((reason, _)) = SKIPPED_LINE_BREAK_TESTS
+                .iter()
+                .find(|(_, lines)| lines.contains(&(line_index + 1)))
+            {
+                assert_ne!(
+                    actual,
+                    expected,
+                    "{path}:{}: skipped but passes ({reason})",
+                    line_index + 1
+                );
+                continue;
+            }
+            assert_eq!(actual, expected, "{path}:{}: {text:?}", line_index + 1);
+            num_cases += 1;
+        }
+        assert!(0 < num_cases, "{path}: no test cases");
     }
 
     #[test]
@@ -316,7 +795,7 @@ mod test {
                 .unwrap();
 
             assert_eq!(
-                line_breaker.next_line_break("1a1a\n"),
+                line_breaker.next_line_break("1aあa\n"),
                 BreakPoint::WrapPoint {
                     overflow_pos: 2,
                     adjustment: 0
@@ -330,10 +809,10 @@ mod test {
                 },
             );
             assert_eq!(
-                line_breaker.next_line_break("※a1a\n"),
+                line_breaker.next_line_break("※ｱｲ\n"),
                 BreakPoint::WrapPoint {
                     overflow_pos: match ambiguous_width {
-                        AmbiguousWidth::Narrow => 4,
+                        AmbiguousWidth::Narrow => 6,
                         AmbiguousWidth::Wide => 3,
                     },
                     adjustment: 0
@@ -374,6 +853,138 @@ mod test {
         Ok(())
     }
 
+    #[rstest]
+    #[case(6, 0, "あいう", BreakPoint::EndOfText(9))]
+    #[case(6, 2, "あいう", BreakPoint::WrapPoint { overflow_pos: 6, adjustment: 0 })]
+    #[case(6, 4, "あいう", BreakPoint::WrapPoint { overflow_pos: 3, adjustment: 0 })]
+    #[case(6, 6, "あいう", BreakPoint::WrapPoint { overflow_pos: 3, adjustment: 0 })]
+    #[case(6, 2, "あ「い", BreakPoint::WrapPoint { overflow_pos: 6, adjustment: 3 })]
+    fn next_line_break_with_indent(
+        #[case] max_width: u32,
+        #[case] indent: u32,
+        #[case] line: &str,
+        #[case] expected: BreakPoint,
+    ) -> anyhow::Result<()> {
+        let line_breaker = LineBreaker::builder().max_width(max_width).build()?;
+        let actual = line_breaker.next_line_break_with_indent(line, indent);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(false, 6, "あいう。え", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 3 })]
+    #[case(true, 6, "あいう。え", BreakPoint::WrapPoint { overflow_pos: 12, adjustment: 0 })]
+    #[case(true, 6, "あいう、\n", BreakPoint::EndOfLine(13))]
+    #[case(true, 6, "あいう。。", BreakPoint::WrapPoint { overflow_pos: 12, adjustment: 6 })]
+    #[case(true, 6, "あいう」え", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 3 })]
+    #[case(true, 5, "あい。うえ", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 0 })]
+    fn next_line_break_hanging_punctuation(
+        #[case] hanging_punctuation: bool,
+        #[case] max_width: u32,
+        #[case] line: &str,
+        #[case] expected: BreakPoint,
+    ) -> anyhow::Result<()> {
+        let line_breaker = LineBreaker::builder()
+            .max_width(max_width)
+            .hanging_punctuation(hanging_punctuation)
+            .build()?;
+        let actual = line_breaker.next_line_break(line);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(KinsokuStrategy::PushOut, 2, 6, "あいう」えお", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 3 })]
+    #[case(KinsokuStrategy::PushIn, 2, 6, "あいう」えお", BreakPoint::WrapPoint { overflow_pos: 12, adjustment: 0 })]
+    #[case(KinsokuStrategy::PushIn, 1, 6, "あいう」えお", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 3 })]
+    #[case(KinsokuStrategy::PushIn, 2, 6, "あいう」」え", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 3 })]
+    #[case(KinsokuStrategy::PushIn, 4, 6, "あいう」」え", BreakPoint::WrapPoint { overflow_pos: 15, adjustment: 0 })]
+    #[case(KinsokuStrategy::PushIn, 2, 6, "あいう」\n", BreakPoint::EndOfLine(13))]
+    #[case(KinsokuStrategy::PushIn, 2, 6, "あいうえお", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 0 })]
+    #[case(KinsokuStrategy::MinRaggedness, 2, 6, "あいう」えお", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 3 })]
+    #[case(
+        KinsokuStrategy::MinRaggedness,
+        2,
+        9,
+        "abc defgh」",
+        BreakPoint::EndOfText(12)
+    )]
+    #[case(KinsokuStrategy::MinRaggedness, 2, 9, "abc defgh」ijklmn", BreakPoint::WrapPoint { overflow_pos: 12, adjustment: 0 })]
+    #[case(KinsokuStrategy::PushOut, 2, 9, "abc defgh」ijklmn", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 5 })]
+    fn next_line_break_strategy(
+        #[case] strategy: KinsokuStrategy,
+        #[case] push_in_tolerance: u32,
+        #[case] max_width: u32,
+        #[case] line: &str,
+        #[case] expected: BreakPoint,
+    ) -> anyhow::Result<()> {
+        let line_breaker = LineBreaker::builder()
+            .max_width(max_width)
+            .strategy(strategy)
+            .push_in_tolerance(push_in_tolerance)
+            .build()?;
+        let actual = line_breaker.next_line_break(line);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(6, &[], "あい……う", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 3 })]
+    #[case(6, &[], "あい――う", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 3 })]
+    #[case(8, &[], "価格¥1,000", BreakPoint::WrapPoint { overflow_pos: 11, adjustment: 5 })]
+    #[case(8, &[], "長さ100 km", BreakPoint::WrapPoint { overflow_pos: 10, adjustment: 4 })]
+    #[case(8, &[], "長さ100 kmph", BreakPoint::WrapPoint { overflow_pos: 10, adjustment: 0 })]
+    #[case(7, &[], "Plan 2 B is", BreakPoint::WrapPoint { overflow_pos: 7, adjustment: 0 })]
+    #[case(6, &[], "あいうえお", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 0 })]
+    #[case(6, &["うえ"], "あいうえお", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 3 })]
+    fn next_line_break_inseparable(
+        #[case] max_width: u32,
+        #[case] inseparable: &[&str],
+        #[case] line: &str,
+        #[case] expected: BreakPoint,
+    ) -> anyhow::Result<()> {
+        let line_breaker = LineBreaker::builder()
+            .max_width(max_width)
+            .inseparable(inseparable.iter().copied())
+            .build()?;
+        let actual = line_breaker.next_line_break(line);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(8, &[], "abc `d e`f", BreakPoint::WrapPoint { overflow_pos: 8, adjustment: 1 })]
+    #[case(8, &[(4, 9)], "abc `d e`f", BreakPoint::WrapPoint { overflow_pos: 8, adjustment: 4 })]
+    #[case(4, &[(0, 9)], "`d e f g` h", BreakPoint::WrapPoint { overflow_pos: 10, adjustment: 0 })]
+    fn next_line_break_with_unbreakable(
+        #[case] max_width: u32,
+        #[case] unbreakable: &[(usize, usize)],
+        #[case] line: &str,
+        #[case] expected: BreakPoint,
+    ) -> anyhow::Result<()> {
+        let unbreakable: Vec<_> = unbreakable.iter().map(|&(start, end)| start..end).collect();
+        let line_breaker = LineBreaker::builder().max_width(max_width).build()?;
+        let actual = line_breaker.next_line_break_with_unbreakable(line, 0, &unbreakable);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(6, "あいう えお", BreakPoint::WrapPoint { overflow_pos: 10, adjustment: 0 })]
+    #[case(6, "あいう  えお", BreakPoint::WrapPoint { overflow_pos: 11, adjustment: 0 })]
+    #[case(6, "あいう  \n", BreakPoint::EndOfLine(12))]
+    #[case(6, "あいうえ お", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 0 })]
+    fn next_line_break_trailing_spaces(
+        #[case] max_width: u32,
+        #[case] line: &str,
+        #[case] expected: BreakPoint,
+    ) -> anyhow::Result<()> {
+        let line_breaker = LineBreaker::builder().max_width(max_width).build()?;
+        let actual = line_breaker.next_line_break(line);
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
     #[rstest]
     #[case(2, "foo\rbar", BreakPoint::EndOfLine(4))]
     #[case(3, "foo\rbar", BreakPoint::EndOfLine(4))]
@@ -414,7 +1025,7 @@ mod test {
     #[case(8, "あfoo barい", BreakPoint::WrapPoint { overflow_pos: 9, adjustment: 2 })]
     #[case(7, "あfoo barい", BreakPoint::WrapPoint { overflow_pos: 8, adjustment: 1 })]
     #[case(6, "あfoo barい", BreakPoint::WrapPoint { overflow_pos: 7, adjustment: 0 })]
-    #[case(5, "あfoo barい", BreakPoint::WrapPoint { overflow_pos: 6, adjustment: 3 })]
+    #[case(5, "あfoo barい", BreakPoint::WrapPoint { overflow_pos: 7, adjustment: 0 })]
     #[case(4, "あfoo barい", BreakPoint::WrapPoint { overflow_pos: 5, adjustment: 2 })]
     #[case(3, "あfoo barい", BreakPoint::WrapPoint { overflow_pos: 4, adjustment: 1 })]
     #[case(2, "あfoo barい", BreakPoint::WrapPoint { overflow_pos: 3, adjustment: 0 })]
@@ -442,10 +1053,10 @@ mod test {
         #[case] following_grapheme: &str,
         #[case] expected: Option<usize>,
     ) -> anyhow::Result<()> {
-        let preceding_graphemes: Vec<&str> = preceding_graphemes.graphemes(true).collect();
+        let line = format!("{preceding_graphemes}{following_grapheme}");
         let line_breaker = LineBreaker::builder().max_width(max_width).build()?;
 
-        let actual = line_breaker.num_bytes_to_seek_back(&preceding_graphemes, following_grapheme);
+        let actual = line_breaker.num_bytes_to_seek_back(&line, preceding_graphemes.len(), &[]);
         assert_eq!(actual, expected);
 
         Ok(())
@@ -4,12 +4,18 @@ mod cli;
 mod config;
 mod document;
 mod format;
+mod kinsoku_checker;
 mod line_break;
 mod markdown_spacing;
+mod markdown_wrap;
+mod normalize;
+mod normalize_checker;
+mod punctuation;
 mod spacing;
 mod spacing_checker;
+mod suppression;
 
-use std::io::stdout;
+use std::{io::stdout, process::ExitCode};
 
 use anyhow::Context;
 use clap::Parser;
@@ -17,14 +23,34 @@ use clap::Parser;
 use crate::{
     cli::{
         args::{self, CliArgs, ColorOutputMode},
-        check::check_command,
+        check::{CheckOptions, check_command},
         debug_cst::debug_cst_command,
-        format::format_command,
+        format::{FormatOptions, format_command},
+        lsp::lsp_command,
     },
     config::Config,
 };
 
-fn main() -> anyhow::Result<()> {
+/// Exit status when `check` reported one or more diagnostics, or when
+/// `format --check` found files that would be changed.
+const EXIT_DIAGNOSTICS_FOUND: u8 = 1;
+
+/// Exit status when a command could not run because of an error.
+///
+/// This matches the status clap uses for command-line usage errors.
+const EXIT_ERROR: u8 = 2;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(exit_code) => exit_code,
+        Err(error) => {
+            eprintln!("Error: {error:?}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn run() -> anyhow::Result<ExitCode> {
     let args = CliArgs::parse();
     let config = Config::from_cli_args(&args).with_context(|| "failed to parse configuration")?;
     let mut stdout = stdout();
@@ -38,18 +64,50 @@ fn main() -> anyhow::Result<()> {
     yansi::whenever(condition);
 
     match args.command {
-        args::Commands::Check { filenames } => {
-            check_command(&mut stdout, &config, filenames.as_slice())?
+        args::Commands::Check {
+            summary,
+            output_format,
+            since,
+            filenames,
+        } => {
+            let options = CheckOptions {
+                summary,
+                output_format,
+                since,
+            };
+            let summary = check_command(&mut stdout, &config, filenames.as_slice(), options)?;
+            if summary.num_diagnostics() > 0 {
+                return Ok(ExitCode::from(EXIT_DIAGNOSTICS_FOUND));
+            }
         }
         args::Commands::DebugCst { filenames } => {
             debug_cst_command(&mut stdout, filenames.as_slice())?
         }
-        args::Commands::Format { write, filenames } => {
-            format_command(&mut stdout, &config, filenames.as_slice(), write)?
+        args::Commands::Lsp => lsp_command(&config)?,
+        args::Commands::Format {
+            write,
+            check,
+            diff,
+            range,
+            lines,
+            since,
+            filenames,
+        } => {
+            let options = FormatOptions {
+                write,
+                check,
+                diff,
+                range: range.or(lines),
+                since,
+            };
+            let num_changed = format_command(&mut stdout, &config, filenames.as_slice(), &options)?;
+            if check && num_changed > 0 {
+                return Ok(ExitCode::from(EXIT_DIAGNOSTICS_FOUND));
+            }
         }
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 
 #[cfg(test)]
@@ -169,6 +227,12 @@ mod file_based_tests {
 
         // Compare the actual output with the expected output
         assert_eq!(String::from_utf8_lossy(&actual), test_case.output);
+
+        // Formatting the output again must not change it
+        let mut reformatted: Vec<u8> = Vec::with_capacity(1024);
+        format_one_file(&mut reformatted, &test_case.config, true, &test_case.output)
+            .unwrap_or_else(|_| panic!("failed on formatting the output: {resource:?}"));
+        assert_eq!(String::from_utf8_lossy(&reformatted), test_case.output);
     }
 
     fn assert_diagnostics_are_equal(a: &Diagnostic, b: &Diagnostic) {
@@ -1,11 +1,13 @@
 use std::ops::Range;
 
 use cjkfmt_parser::{Grammar, parse};
-use tree_sitter::Node;
+use tree_sitter::{Node, Tree};
 
 use crate::{
-    config::Config,
-    spacing::{TextEdit, spacing_edits},
+    config::{Config, SpacingRule},
+    normalize::normalize_edits,
+    spacing::{TextEdit, pair_rule, spacing_edits},
+    suppression::Suppressions,
 };
 
 const EXCLUDED_NODE_KINDS: &[&str] = &[
@@ -25,7 +27,84 @@ const EXCLUDED_NODE_KINDS: &[&str] = &[
 /// Applies configured spacing rules to Markdown prose while preserving inline
 /// constructs whose contents are not displayed as ordinary prose.
 pub(crate) fn apply_markdown_spacing(config: &Config, source: &str) -> anyhow::Result<String> {
+    apply_markdown_inline_edits(source, |text, exclusions| {
+        // Spacing edits fix what the checker reports as W002. Markup boundary
+        // edits are made right outside of code spans and other excluded
+        // constructs by design, so only the other spacing edits are filtered.
+        spacing_edits(config, text)
+            .into_iter()
+            .filter(|edit| !intersects_any(&edit.range, exclusions))
+            .chain(markup_boundary_edits(config, text))
+            .map(|edit| ("W002", edit))
+            .collect()
+    })
+}
+
+/// Applies configured normalization rules to Markdown prose in the same way
+/// as [`apply_markdown_spacing`].
+pub(crate) fn apply_markdown_normalization(
+    config: &Config,
+    source: &str,
+) -> anyhow::Result<String> {
+    apply_markdown_inline_edits(source, |text, exclusions| {
+        without_excluded(normalize_edits(config, text, exclusions), exclusions)
+    })
+}
+
+/// Applies the edits `inline_edits` returns for Markdown inline nodes, except
+/// for the ones whose diagnostic code is suppressed by a comment.
+fn apply_markdown_inline_edits<F>(source: &str, inline_edits: F) -> anyhow::Result<String>
+where
+    F: Fn(&str, &[Range<usize>]) -> Vec<(&'static str, TextEdit)>,
+{
     let block_tree = parse(Grammar::Markdown, source)?;
+    let suppressions = Suppressions::new(source, &block_tree)?;
+    if suppressions.ignores_file() {
+        return Ok(source.to_owned());
+    }
+
+    let edits = markdown_inline_edits(source, &block_tree, inline_edits)?
+        .into_iter()
+        .filter(|(code, edit)| !suppressions.is_suppressed_at(edit.range.start, code))
+        .map(|(_, edit)| edit)
+        .collect();
+    apply_text_edits(source, edits)
+}
+
+/// Returns the edits `prose_edits` returns for the prose of a Markdown block
+/// tree, with byte ranges in the whole source.
+///
+/// Besides the source of each inline node, `prose_edits` receives the byte
+/// ranges of the inline constructs in it whose contents are not displayed as
+/// ordinary prose, such as code spans and link destinations. Edits touching
+/// them are left out.
+pub(crate) fn markdown_prose_edits<F>(
+    source: &str,
+    block_tree: &Tree,
+    prose_edits: F,
+) -> anyhow::Result<Vec<(&'static str, TextEdit)>>
+where
+    F: Fn(&str, &[Range<usize>]) -> Vec<(&'static str, TextEdit)>,
+{
+    markdown_inline_edits(source, block_tree, |text, exclusions| {
+        without_excluded(prose_edits(text, exclusions), exclusions)
+    })
+}
+
+/// Returns the edits `inline_edits` returns for the inline nodes of a Markdown
+/// block tree, with byte ranges in the whole source.
+///
+/// Besides the source of each inline node, `inline_edits` receives the byte
+/// ranges of the inline constructs in it that are not ordinary prose. Inline
+/// nodes that cannot be parsed safely are skipped.
+fn markdown_inline_edits<F>(
+    source: &str,
+    block_tree: &Tree,
+    inline_edits: F,
+) -> anyhow::Result<Vec<(&'static str, TextEdit)>>
+where
+    F: Fn(&str, &[Range<usize>]) -> Vec<(&'static str, TextEdit)>,
+{
     let mut inline_ranges = Vec::new();
     collect_inline_ranges(block_tree.root_node(), &mut inline_ranges);
 
@@ -54,21 +133,260 @@ pub(crate) fn apply_markdown_spacing(config: &Config, source: &str) -> anyhow::R
         );
         merge_ranges(&mut exclusions);
 
-        for edit in spacing_edits(config, inline_source) {
-            if !exclusions
-                .iter()
-                .any(|exclusion| edit_intersects(&edit.range, exclusion))
-            {
-                edits.push(TextEdit {
+        for (code, edit) in inline_edits(inline_source, &exclusions) {
+            edits.push((
+                code,
+                TextEdit {
                     range: (inline_range.start + edit.range.start)
                         ..(inline_range.start + edit.range.end),
                     replacement: edit.replacement,
-                });
+                },
+            ));
+        }
+    }
+    Ok(edits)
+}
+
+fn without_excluded(
+    edits: Vec<(&'static str, TextEdit)>,
+    exclusions: &[Range<usize>],
+) -> Vec<(&'static str, TextEdit)> {
+    edits
+        .into_iter()
+        .filter(|(_, edit)| !intersects_any(&edit.range, exclusions))
+        .collect()
+}
+
+/// Markdown inline syntax around which the spacing rules apply differently
+/// from plain prose.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Markup {
+    /// Syntax before the visible content of an element, such as `**` of
+    /// strong emphasis or `[` of a link.
+    Opening,
+
+    /// Syntax after the visible content of an element, such as `](url)` of a
+    /// link.
+    Closing,
+
+    /// A code span with the byte range of its content.
+    CodeSpan(Range<usize>),
+
+    /// Content that is not prose at all, such as an HTML tag.
+    Opaque,
+}
+
+/// Returns the spacing edits needed at the boundaries of inline Markdown
+/// syntax, which [`spacing_edits`] leaves alone as the syntax characters are
+/// not letters.
+///
+/// A code span counts as its first and last visible characters, with
+/// `spacing.inline_code` as the rule between them and prose. If
+/// `spacing.markup_boundaries` is enabled, emphasis delimiters and link syntax
+/// are looked through, and a space is inserted outside of them. `source` must
+/// be the source of an inline node; nothing is changed if it cannot be parsed
+/// safely.
+pub(crate) fn markup_boundary_edits(config: &Config, source: &str) -> Vec<TextEdit> {
+    if config.spacing.inline_code == SpacingRule::Ignore && !config.spacing.markup_boundaries {
+        return Vec::new();
+    }
+    let Ok(tree) = parse(Grammar::MarkdownInline, source) else {
+        return Vec::new();
+    };
+    if tree.root_node().has_error() || !is_safe_inline_tree(tree.root_node(), source) {
+        return Vec::new();
+    }
+    let mut markups = Vec::new();
+    collect_markups(tree.root_node(), &mut markups);
+
+    let mut edits = Vec::new();
+    let mut boundary = Boundary::default();
+    let mut markups = markups.into_iter().peekable();
+    let mut position = 0;
+    while position < source.len() {
+        // Syntax never overlaps, but do not get stuck if a grammar revision
+        // happens to nest some.
+        while markups
+            .next_if(|(range, _)| range.start < position)
+            .is_some()
+        {}
+        if let Some((range, markup)) = markups.next_if(|(range, _)| range.start == position) {
+            position = range.end;
+            match markup {
+                Markup::Opening | Markup::Closing if config.spacing.markup_boundaries => {
+                    let closing = markup == Markup::Closing;
+                    boundary.markups.push((range, closing));
+                }
+                Markup::CodeSpan(content) => {
+                    let content = source[content].trim_matches([' ', '\r', '\n']);
+                    if let (Some(first), Some(last)) =
+                        (content.chars().next(), content.chars().next_back())
+                    {
+                        boundary.visit(config, &mut edits, range, first, last, true);
+                    } else {
+                        boundary = Boundary::default();
+                    }
+                }
+                _ => boundary = Boundary::default(),
             }
+            continue;
+        }
+
+        let c = source[position..].chars().next().unwrap_or_default();
+        let range = position..position + c.len_utf8();
+        position = range.end;
+        if c == ' ' {
+            match boundary.spaces.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => boundary.spaces.push(range),
+            }
+        } else {
+            boundary.visit(config, &mut edits, range, c, c, false);
         }
     }
+    edits
+}
 
-    apply_text_edits(source, edits)
+/// What lies between the last visible piece of prose and the next one.
+#[derive(Debug, Default)]
+struct Boundary {
+    /// The last visible character, whether it ends a code span, and the byte
+    /// offset after it.
+    previous: Option<(char, bool, usize)>,
+
+    /// Syntax since the previous character, and whether each is closing.
+    markups: Vec<(Range<usize>, bool)>,
+
+    /// Runs of ASCII spaces since the previous character.
+    spaces: Vec<Range<usize>>,
+}
+
+impl Boundary {
+    /// Adds the edits needed between the previous visible piece of prose and
+    /// the next one, which starts with `first` and ends with `last`.
+    fn visit(
+        &mut self,
+        config: &Config,
+        edits: &mut Vec<TextEdit>,
+        range: Range<usize>,
+        first: char,
+        last: char,
+        code_span: bool,
+    ) {
+        if let Some((previous, previous_code_span, end)) = self.previous
+            && (code_span || previous_code_span || !self.markups.is_empty())
+        {
+            let rule = pair_rule(config, previous, first).map(|rule| {
+                if code_span || previous_code_span {
+                    config.spacing.inline_code
+                } else {
+                    rule
+                }
+            });
+            match rule {
+                Some(SpacingRule::Require) if self.spaces.is_empty() => {
+                    // Insert outside of the syntax of both sides.
+                    let position = self
+                        .markups
+                        .iter()
+                        .rfind(|(_, closing)| *closing)
+                        .map_or(end, |(range, _)| range.end);
+                    edits.push(TextEdit {
+                        range: position..position,
+                        replacement: " ".to_string(),
+                    });
+                }
+                Some(SpacingRule::Prohibit) => {
+                    edits.extend(self.spaces.drain(..).map(|range| TextEdit {
+                        range,
+                        replacement: String::new(),
+                    }));
+                }
+                _ => {}
+            }
+        }
+        *self = Boundary {
+            previous: Some((last, code_span, range.end)),
+            ..Default::default()
+        };
+    }
+}
+
+/// Collects the inline syntax in the order of appearance.
+fn collect_markups(node: Node<'_>, markups: &mut Vec<(Range<usize>, Markup)>) {
+    match node.kind() {
+        "code_span" => {
+            let mut cursor = node.walk();
+            let delimiters: Vec<_> = node
+                .children(&mut cursor)
+                .filter(|child| child.kind() == "code_span_delimiter")
+                .collect();
+            let content = match delimiters.as_slice() {
+                [opening, .., closing] => opening.end_byte()..closing.start_byte(),
+                _ => node.start_byte()..node.start_byte(),
+            };
+            markups.push((node.byte_range(), Markup::CodeSpan(content)));
+        }
+        kind if EXCLUDED_NODE_KINDS.contains(&kind) => {
+            markups.push((node.byte_range(), Markup::Opaque));
+        }
+        "emphasis" | "strong_emphasis" | "strikethrough" => {
+            collect_element_markups(node, |child| child.kind() == "emphasis_delimiter", markups);
+        }
+        "inline_link"
+        | "image"
+        | "full_reference_link"
+        | "collapsed_reference_link"
+        | "shortcut_link" => {
+            collect_element_markups(
+                node,
+                |child| !matches!(child.kind(), "link_text" | "image_description"),
+                markups,
+            );
+        }
+        _ => {
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                collect_markups(child, markups);
+            }
+        }
+    }
+}
+
+/// Collects the syntax of an element, where the children for which
+/// `is_syntax` returns `true` are syntax and the others are its content.
+fn collect_element_markups<F>(
+    node: Node<'_>,
+    is_syntax: F,
+    markups: &mut Vec<(Range<usize>, Markup)>,
+) where
+    F: Fn(&Node<'_>) -> bool,
+{
+    // Syntax before any content, even if the content is plain text without a
+    // node of its own, is opening. Syntax after it is closing, along with the
+    // spaces between, such as the one before a link title.
+    let mut opening_end = Some(node.start_byte());
+    let mut closing: Option<Range<usize>> = None;
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if !is_syntax(&child) {
+            opening_end = None;
+            if let Some(range) = closing.take() {
+                markups.push((range, Markup::Closing));
+            }
+            collect_markups(child, markups);
+        } else if opening_end == Some(child.start_byte()) {
+            opening_end = Some(child.end_byte());
+            markups.push((child.byte_range(), Markup::Opening));
+        } else {
+            opening_end = None;
+            closing =
+                Some(closing.map_or(child.start_byte(), |range| range.start)..child.end_byte());
+        }
+    }
+    if let Some(range) = closing {
+        markups.push((range, Markup::Closing));
+    }
 }
 
 fn collect_inline_ranges(node: Node<'_>, ranges: &mut Vec<Range<usize>>) {
@@ -153,6 +471,15 @@ fn collect_exclusion_ranges(node: Node<'_>, ranges: &mut Vec<Range<usize>>) {
         return;
     }
 
+    // The parentheses around the destination of a link or an image are syntax
+    // rather than prose, although they are not nodes of their own kind.
+    if matches!(node.kind(), "inline_link" | "image") {
+        let mut cursor = node.walk();
+        if let Some(opening) = node.children(&mut cursor).find(|child| child.kind() == "(") {
+            ranges.push(opening.start_byte()..node.end_byte());
+        }
+    }
+
     let mut cursor = node.walk();
     for child in node.named_children(&mut cursor) {
         collect_exclusion_ranges(child, ranges);
@@ -174,6 +501,12 @@ fn merge_ranges(ranges: &mut Vec<Range<usize>>) {
     *ranges = merged;
 }
 
+fn intersects_any(edit: &Range<usize>, exclusions: &[Range<usize>]) -> bool {
+    exclusions
+        .iter()
+        .any(|exclusion| edit_intersects(edit, exclusion))
+}
+
 fn edit_intersects(edit: &Range<usize>, exclusion: &Range<usize>) -> bool {
     if edit.is_empty() {
         exclusion.start <= edit.start && edit.start < exclusion.end
@@ -182,7 +515,7 @@ fn edit_intersects(edit: &Range<usize>, exclusion: &Range<usize>) -> bool {
     }
 }
 
-fn apply_text_edits(source: &str, mut edits: Vec<TextEdit>) -> anyhow::Result<String> {
+pub(crate) fn apply_text_edits(source: &str, mut edits: Vec<TextEdit>) -> anyhow::Result<String> {
     edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
     for edit in &edits {
         if edit.range.start > edit.range.end
@@ -222,8 +555,10 @@ fn apply_text_edits(source: &str, mut edits: Vec<TextEdit>) -> anyhow::Result<St
 
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
+
     use super::*;
-    use crate::config::SpacingRule;
+    use crate::config::Parentheses;
 
     fn config(alphabets: SpacingRule, digits: SpacingRule) -> Config {
         let mut config = Config {
@@ -239,6 +574,35 @@ mod tests {
         apply_markdown_spacing(&config(alphabets, digits), source).unwrap()
     }
 
+    #[test]
+    fn suppressed_lines_are_not_formatted() {
+        let source = concat!(
+            "漢A\n",
+            "\n",
+            "<!-- cjkfmt-disable W002 -->\n",
+            "漢B\n",
+            "<!-- cjkfmt-enable -->\n",
+            "漢C\n",
+            "\n",
+            "漢D <!-- cjkfmt-disable-next-line -->\n",
+            "漢E\n",
+        );
+        assert_eq!(
+            format(source, SpacingRule::Require, SpacingRule::Ignore),
+            concat!(
+                "漢 A\n",
+                "\n",
+                "<!-- cjkfmt-disable W002 -->\n",
+                "漢B\n",
+                "<!-- cjkfmt-enable -->\n",
+                "漢 C\n",
+                "\n",
+                "漢 D <!-- cjkfmt-disable-next-line -->\n",
+                "漢E\n",
+            )
+        );
+    }
+
     #[test]
     fn formats_prose_inside_inline_markdown_constructs() {
         assert_eq!(
@@ -260,6 +624,100 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case(
+        SpacingRule::Require,
+        "漢`code`漢 `漢字`A 漢`--x` 漢` 1 `",
+        "漢 `code` 漢 `漢字` A 漢`--x` 漢 ` 1 `"
+    )]
+    #[case(
+        SpacingRule::Prohibit,
+        "漢 `code`  漢 `漢字` A *漢* `a`",
+        "漢`code`漢 `漢字`A *漢* `a`"
+    )]
+    #[case(SpacingRule::Ignore, "漢`code` 漢", "漢`code` 漢")]
+    fn inline_code_rule_applies_between_code_spans_and_prose(
+        #[case] rule: SpacingRule,
+        #[case] source: &str,
+        #[case] expected: &str,
+    ) {
+        let mut config = config(SpacingRule::Ignore, SpacingRule::Ignore);
+        config.spacing.inline_code = rule;
+        assert_eq!(apply_markdown_spacing(&config, source).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        SpacingRule::Require,
+        "**強調**A 漢*A* [漢](https://e.test)A A[漢][ref] ~~漢~~A ![漢](a.png \"t\")A",
+        "**強調** A 漢 *A* [漢](https://e.test) A A [漢][ref] ~~漢~~ A ![漢](a.png \"t\") A"
+    )]
+    #[case(
+        SpacingRule::Prohibit,
+        "漢 **A** [漢](u) A *漢* 1",
+        "漢**A**[漢](u)A*漢* 1"
+    )]
+    fn markup_boundaries_look_through_emphasis_and_links(
+        #[case] rule: SpacingRule,
+        #[case] source: &str,
+        #[case] expected: &str,
+    ) {
+        let mut config = config(rule, SpacingRule::Ignore);
+        assert_eq!(apply_markdown_spacing(&config, source).unwrap(), source);
+
+        config.spacing.markup_boundaries = true;
+        assert_eq!(apply_markdown_spacing(&config, source).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(2..2, 2..4, true)]
+    #[case(3..3, 2..4, true)]
+    #[case(4..4, 2..4, false)]
+    #[case(1..1, 2..4, false)]
+    #[case(1..2, 2..4, false)]
+    #[case(1..3, 2..4, true)]
+    fn edits_intersect_excluded_ranges(
+        #[case] edit: Range<usize>,
+        #[case] exclusion: Range<usize>,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(edit_intersects(&edit, &exclusion), expected);
+    }
+
+    #[test]
+    fn markup_boundaries_do_not_look_through_other_syntax() {
+        let mut config = config(SpacingRule::Require, SpacingRule::Require);
+        config.spacing.markup_boundaries = true;
+        for source in ["漢<b>A</b>", "漢&amp;A", "漢\\*A", "**漢**\nA", "漢**\tA**"] {
+            assert_eq!(
+                apply_markdown_spacing(&config, source).unwrap(),
+                source,
+                "changed {source:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn normalizes_parentheses_but_not_link_destinations() {
+        let mut config = Config::default();
+        config.punctuation.parentheses = Parentheses::FullwidthAroundCjk;
+        let source = "[漢](https://例.test) ![漢](漢.png \"漢\") 漢(A) `漢(A)`";
+        assert_eq!(
+            apply_markdown_normalization(&config, source).unwrap(),
+            "[漢](https://例.test) ![漢](漢.png \"漢\") 漢（A） `漢(A)`"
+        );
+    }
+
+    #[test]
+    fn pairs_parentheses_around_code_spans() {
+        let mut config = Config::default();
+        config.punctuation.parentheses = Parentheses::FullwidthAroundCjk;
+        assert_eq!(
+            apply_markdown_normalization(&config, "日本語(`code)`)です。").unwrap(),
+            "日本語（`code)`）です。"
+        );
+    }
+
     #[test]
     fn preserves_non_prose_inline_ranges() {
         let source = concat!(
@@ -0,0 +1,442 @@
+use std::ops::Range;
+
+use cjkfmt_core::lines_inclusive::LinesInclusiveExt;
+use cjkfmt_parser::{Grammar, parse};
+use tree_sitter::Node;
+
+use crate::{
+    config::Config,
+    markdown_spacing::apply_text_edits,
+    spacing::{TextEdit, join_separator},
+    suppression::Suppressions,
+};
+
+/// Inline nodes which must not be broken across lines.
+///
+/// A line break inside a code span changes its content, and one inside an
+/// autolink or a link destination breaks the link. A line break before a hard
+/// line break would leave the backslash or the spaces on a line of their own.
+const UNBREAKABLE_NODE_KINDS: &[&str] = &[
+    "code_span",
+    "uri_autolink",
+    "email_autolink",
+    "link_destination",
+    "hard_line_break",
+];
+
+/// Schemes and prefixes starting a bare URL in prose.
+const BARE_URL_PREFIXES: &[&str] = &["https://", "http://", "ftp://", "www."];
+
+/// Paragraph prose in a Markdown document that may be wrapped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WrappableRange {
+    /// UTF-8 byte range of the paragraph's inline content.
+    pub(crate) range: Range<usize>,
+
+    /// Prefix to write at the start of each line added by wrapping, so that
+    /// the line stays inside the paragraph's list items and block quotes.
+    pub(crate) prefix: String,
+
+    /// UTF-8 byte ranges of the container prefixes at the start of the
+    /// paragraph's lines after the first one.
+    pub(crate) continuations: Vec<Range<usize>>,
+
+    /// UTF-8 byte ranges of inline content which must not be broken across
+    /// lines, such as code spans and URLs.
+    pub(crate) unbreakable: Vec<Range<usize>>,
+}
+
+/// Returns the Markdown paragraph prose that may be wrapped.
+///
+/// Only the `inline` content of ordinary paragraphs is returned. Headings,
+/// code blocks, tables, link reference definitions, HTML blocks and any other
+/// block kinds must be preserved byte-for-byte, so they are never included.
+pub(crate) fn wrappable_ranges(source: &str) -> anyhow::Result<Vec<WrappableRange>> {
+    let tree = parse(Grammar::Markdown, source)?;
+    let mut inlines = Vec::new();
+    collect_paragraph_inlines(tree.root_node(), &mut inlines);
+    inlines
+        .into_iter()
+        .map(|inline| {
+            let range = inline.byte_range();
+            let text = &source[range.clone()];
+            let inline_tree = parse(Grammar::MarkdownInline, text)?;
+            let mut unbreakable = Vec::new();
+            collect_unbreakable_ranges(inline_tree.root_node(), text, &mut unbreakable);
+            let bare_urls: Vec<_> = bare_url_ranges(text)
+                .into_iter()
+                .filter(|url| {
+                    !unbreakable
+                        .iter()
+                        .any(|node| url.start < node.end && node.start < url.end)
+                })
+                .collect();
+            unbreakable.extend(bare_urls);
+            Ok(WrappableRange {
+                unbreakable: unbreakable
+                    .into_iter()
+                    .map(|unbreakable| {
+                        (range.start + unbreakable.start)..(range.start + unbreakable.end)
+                    })
+                    .collect(),
+                continuations: continuation_ranges(inline)
+                    .into_iter()
+                    .map(|continuation| {
+                        (range.start + continuation.start)..(range.start + continuation.end)
+                    })
+                    .collect(),
+                range,
+                prefix: continuation_prefix(inline),
+            })
+        })
+        .collect()
+}
+
+/// Joins the soft-wrapped lines of each Markdown paragraph into a single line.
+///
+/// Hard line breaks (two or more trailing spaces, or a trailing backslash) are
+/// kept. The container prefixes of the joined lines, such as `> ` in a block
+/// quote or the indentation of a list item, are removed along with the breaks.
+/// Lines on which W001 is suppressed are not joined with their neighbours.
+pub(crate) fn join_paragraph_lines(config: &Config, source: &str) -> anyhow::Result<String> {
+    let tree = parse(Grammar::Markdown, source)?;
+    let suppressions = Suppressions::new(source, &tree)?;
+    let mut inlines = Vec::new();
+    collect_paragraph_inlines(tree.root_node(), &mut inlines);
+
+    let mut edits = Vec::new();
+    for inline in inlines {
+        let range = inline.byte_range();
+        let continuations = continuation_ranges(inline);
+        for edit in soft_break_edits(config, &source[range.clone()], &continuations) {
+            let edit_range = (range.start + edit.range.start)..(range.start + edit.range.end);
+            if suppressions.is_suppressed_at(edit_range.start, "W001")
+                || suppressions.is_suppressed_at(edit_range.end, "W001")
+            {
+                continue;
+            }
+            edits.push(TextEdit {
+                range: edit_range,
+                replacement: edit.replacement,
+            });
+        }
+    }
+
+    apply_text_edits(source, edits)
+}
+
+fn soft_break_edits(config: &Config, text: &str, continuations: &[Range<usize>]) -> Vec<TextEdit> {
+    let mut edits = Vec::new();
+    let mut line_start = 0;
+    for line in text.lines_inclusive() {
+        let line_end = line_start + line.len();
+        let content = line.trim_end_matches(['\r', '\n']);
+        if content.len() == line.len() || is_hard_line_break(content) {
+            line_start = line_end;
+            continue;
+        }
+
+        // Skip the container prefix of the following line, if any
+        let prefix_end = continuations
+            .iter()
+            .find(|continuation| continuation.start == line_end)
+            .map_or(line_end, |continuation| continuation.end);
+
+        let body = content.trim_end_matches([' ', '\t']);
+        let following = &text[prefix_end..];
+        let next_line = following.trim_start_matches([' ', '\t']);
+        if let (Some(left), Some(right)) = (body.chars().next_back(), next_line.chars().next()) {
+            let leading = following.len() - next_line.len();
+            edits.push(TextEdit {
+                range: (line_start + body.len())..(prefix_end + leading),
+                replacement: join_separator(config, left, right).to_string(),
+            });
+        }
+        line_start = line_end;
+    }
+    edits
+}
+
+fn is_hard_line_break(content: &str) -> bool {
+    let backslashes = content.len() - content.trim_end_matches('\\').len();
+    content.ends_with("  ") || backslashes % 2 == 1
+}
+
+/// Returns the byte ranges of the container prefixes inside an inline node,
+/// relative to the start of the node.
+pub(crate) fn continuation_ranges(inline: Node<'_>) -> Vec<Range<usize>> {
+    let start = inline.start_byte();
+    let mut cursor = inline.walk();
+    inline
+        .named_children(&mut cursor)
+        .filter(|child| child.kind() == "block_continuation")
+        .map(|child| (child.start_byte() - start)..(child.end_byte() - start))
+        .collect()
+}
+
+/// Builds the prefix for lines added inside the containers of a node.
+///
+/// Block quotes contribute `> ` and list items contribute spaces as wide as
+/// their list marker, from the outermost container to the innermost one.
+fn continuation_prefix(node: Node<'_>) -> String {
+    let mut parts = Vec::new();
+    let mut ancestor = node.parent();
+    while let Some(container) = ancestor {
+        match container.kind() {
+            "block_quote" => parts.push("> ".to_string()),
+            "list_item" => {
+                let mut cursor = container.walk();
+                let marker = container
+                    .named_children(&mut cursor)
+                    .find(|child| child.kind().starts_with("list_marker"));
+                if let Some(marker) = marker {
+                    parts.push(" ".repeat(marker.byte_range().len()));
+                }
+            }
+            _ => {}
+        }
+        ancestor = container.parent();
+    }
+    parts.reverse();
+    parts.concat()
+}
+
+fn collect_unbreakable_ranges(node: Node<'_>, source: &str, ranges: &mut Vec<Range<usize>>) {
+    if UNBREAKABLE_NODE_KINDS.contains(&node.kind()) {
+        let mut range = node.byte_range();
+        // The `](` before a link destination cannot be separated either.
+        if node.kind() == "link_destination"
+            && let Some(bracket) = source[..range.start].rfind(']')
+        {
+            range.start = bracket;
+        }
+        // Nor can a hard line break be separated from the text before it.
+        if node.kind() == "hard_line_break" {
+            range.start = range.start.saturating_sub(1);
+        }
+        ranges.push(range);
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_unbreakable_ranges(child, source, ranges);
+    }
+}
+
+/// Returns the byte ranges of URLs written in prose without angle brackets.
+///
+/// As with GFM extended autolinks, a URL ends at whitespace or `<`, and
+/// punctuation marks at its end are not part of it. It also ends at a
+/// non-ASCII character, as URLs in CJK prose are not separated by spaces.
+fn bare_url_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut search_start = 0;
+    while search_start < text.len() {
+        let Some((start, prefix)) = BARE_URL_PREFIXES
+            .iter()
+            .filter_map(|prefix| {
+                text[search_start..]
+                    .find(prefix)
+                    .map(|index| (search_start + index, prefix))
+            })
+            .min()
+        else {
+            break;
+        };
+        let url = &text[start..];
+        let url = &url[..url
+            .find(|c: char| !c.is_ascii_graphic() || c == '<')
+            .unwrap_or(url.len())];
+        let url = url.trim_end_matches(['?', '!', '.', ',', ':', '*', '_', '~', '\'', '"']);
+
+        // A URL must follow a word boundary and have something after its prefix.
+        let at_boundary = !text[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric());
+        if at_boundary && prefix.len() < url.len() {
+            ranges.push(start..start + url.len());
+        }
+        search_start = start + url.len().max(prefix.len());
+    }
+    ranges
+}
+
+fn collect_paragraph_inlines<'tree>(node: Node<'tree>, inlines: &mut Vec<Node<'tree>>) {
+    if node.kind() == "paragraph" {
+        // The content of a setext heading is a paragraph node in the CST, but
+        // wrapping it would turn the heading into a different block.
+        if node
+            .parent()
+            .is_some_and(|parent| parent.kind() == "setext_heading")
+        {
+            return;
+        }
+
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            if child.kind() == "inline" {
+                inlines.push(child);
+            }
+        }
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_paragraph_inlines(child, inlines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SpacingRule;
+
+    fn wrappable_texts(source: &str) -> Vec<&str> {
+        wrappable_ranges(source)
+            .unwrap()
+            .into_iter()
+            .map(|wrappable| &source[wrappable.range])
+            .collect()
+    }
+
+    fn prefixes(source: &str) -> Vec<String> {
+        wrappable_ranges(source)
+            .unwrap()
+            .into_iter()
+            .map(|wrappable| wrappable.prefix)
+            .collect()
+    }
+
+    #[test]
+    fn collects_paragraph_prose_only() {
+        let source = concat!(
+            "# Heading\n",
+            "\n",
+            "Title\n",
+            "=====\n",
+            "\n",
+            "para one\n",
+            "para two\n",
+            "\n",
+            "```\n",
+            "code\n",
+            "```\n",
+            "\n",
+            "| a | b |\n",
+            "|---|---|\n",
+            "\n",
+            "[label]: https://example.test\n",
+            "\n",
+            "<div>\n",
+            "html\n",
+            "</div>\n",
+            "\n",
+            "    indented\n",
+        );
+        assert_eq!(wrappable_texts(source), ["para one\npara two"]);
+    }
+
+    #[test]
+    fn collects_paragraphs_nested_in_containers() {
+        let source = "- item\n\n> quote\n";
+        assert_eq!(wrappable_texts(source), ["item", "quote"]);
+    }
+
+    fn unbreakable_texts(source: &str) -> Vec<&str> {
+        wrappable_ranges(source)
+            .unwrap()
+            .into_iter()
+            .flat_map(|wrappable| wrappable.unbreakable)
+            .map(|range| &source[range])
+            .collect()
+    }
+
+    #[test]
+    fn collects_code_spans_links_and_urls_as_unbreakable() {
+        let source = concat!(
+            "> `a b` <https://a.test/x> [link](https://b.test/y \"title\")\n",
+            "> 詳細はhttps://c.test/z?q=1を参照。www.d.test. ttps://e.test\n",
+        );
+        assert_eq!(
+            unbreakable_texts(source),
+            [
+                "`a b`",
+                "<https://a.test/x>",
+                "](https://b.test/y",
+                "https://c.test/z?q=1",
+                "www.d.test",
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_soft_line_breaks_according_to_the_characters_on_both_sides() {
+        let config = Config::default();
+        assert_eq!(
+            join_paragraph_lines(&config, "漢字の\nかな\nand\n  text\n").unwrap(),
+            "漢字のかな and text\n"
+        );
+    }
+
+    #[test]
+    fn joins_mixed_pairs_without_space_when_spacing_is_prohibited() {
+        let mut config = Config::default();
+        config.spacing.alphabets = SpacingRule::Prohibit;
+        assert_eq!(
+            join_paragraph_lines(&config, "漢字\nABC\n").unwrap(),
+            "漢字ABC\n"
+        );
+    }
+
+    #[test]
+    fn keeps_hard_line_breaks_and_paragraph_boundaries() {
+        let config = Config::default();
+        let source = "一  \n二\\\n三\n四\n\n五\n六\r\n七\n";
+        assert_eq!(
+            join_paragraph_lines(&config, source).unwrap(),
+            "一  \n二\\\n三四\n\n五六七\n"
+        );
+    }
+
+    #[test]
+    fn joins_lines_inside_containers_without_their_prefixes() {
+        let config = Config::default();
+        let source = "- 一\n  二\n\n> 三\n> 四\n\n> - 五\n>   六\n";
+        assert_eq!(
+            join_paragraph_lines(&config, source).unwrap(),
+            "- 一二\n\n> 三四\n\n> - 五六\n"
+        );
+    }
+
+    #[test]
+    fn does_not_join_lines_outside_paragraphs() {
+        let config = Config::default();
+        let source = "```\n五\n六\n```\n";
+        assert_eq!(join_paragraph_lines(&config, source).unwrap(), source);
+    }
+
+    #[test]
+    fn builds_continuation_prefixes_of_nested_containers() {
+        let source = concat!(
+            "top\n",
+            "\n",
+            "- item\n",
+            "\n",
+            "10. item\n",
+            "\n",
+            "> quote\n",
+            "\n",
+            "> - quoted item\n",
+            ">   1. nested\n",
+            "\n",
+            "-   > wide marker\n",
+        );
+        assert_eq!(
+            prefixes(source),
+            ["", "  ", "    ", "> ", ">   ", ">      ", "    > "]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Normalization of full-width and half-width variants of characters.
+use std::ops::Range;
+
+use crate::{
+    config::{Config, FullwidthAlphanumerics, FullwidthSpace, HalfwidthKatakana},
+    punctuation::{self, punctuation_edits, punctuation_message},
+    spacing::TextEdit,
+};
+
+/// Diagnostic code of full-width alphabets and digits to replace.
+pub(crate) const FULLWIDTH_ALPHANUMERICS: &str = "W005";
+
+/// Diagnostic code of half-width katakana to replace.
+pub(crate) const HALFWIDTH_KATAKANA: &str = "W006";
+
+/// Diagnostic code of full-width spaces to replace.
+pub(crate) const FULLWIDTH_SPACE: &str = "W007";
+
+/// Full-width forms of the characters from U+FF61 to U+FF9F, in order.
+const FULLWIDTH_KATAKANA: &str = concat!(
+    "。「」、・ヲァィゥェォャュョッー",
+    "アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン",
+    "゛゜",
+);
+
+/// Katakana which have voiced forms, and the forms in the same order.
+const UNVOICED: &str = "ウカキクケコサシスセソタチツテトハヒフヘホ";
+const VOICED: &str = "ヴガギグゲゴザジズゼゾダヂヅデドバビブベボ";
+
+/// Katakana which have semi-voiced forms, and the forms in the same order.
+const UNSEMIVOICED: &str = "ハヒフヘホ";
+const SEMIVOICED: &str = "パピプペポ";
+
+/// Returns the normalization edits needed for a piece of text, each with the
+/// diagnostic code of the rule requiring it.
+///
+/// The edits of the punctuation style rules are included, for which
+/// `excluded` are the byte ranges of `text` that are not prose. The ranges are
+/// UTF-8 byte ranges relative to `text`, in order. A run of characters
+/// replaced by the same rule is handled as one edit.
+pub(crate) fn normalize_edits(
+    config: &Config,
+    text: &str,
+    excluded: &[Range<usize>],
+) -> Vec<(&'static str, TextEdit)> {
+    let mut edits: Vec<(&'static str, TextEdit)> = Vec::new();
+    let mut characters = text.char_indices().peekable();
+    while let Some((start, c)) = characters.next() {
+        let mut end = start + c.len_utf8();
+        let (code, replacement) = match c {
+            'Ａ'..='Ｚ' | 'ａ'..='ｚ' | '０'..='９'
+                if config.normalize.fullwidth_alphanumerics
+                    == FullwidthAlphanumerics::ToHalfwidth =>
+            {
+                let halfwidth = char::from_u32(c as u32 - 0xFEE0).unwrap_or(c);
+                (FULLWIDTH_ALPHANUMERICS, halfwidth.to_string())
+            }
+            // The half-width `｡` and `､` are left to the punctuation style
+            // rules if they replace them.
+            '\u{FF61}'..='\u{FF9F}'
+                if config.normalize.halfwidth_katakana == HalfwidthKatakana::ToFullwidth
+                    && !punctuation::replaces(config, c) =>
+            {
+                let mut fullwidth = fullwidth_katakana(c);
+
+                // A following sound mark is combined if the katakana has such a form.
+                if let Some(&(mark_start, mark)) = characters.peek()
+                    && let Some(combined) = combine_sound_mark(fullwidth, mark)
+                {
+                    fullwidth = combined;
+                    end = mark_start + mark.len_utf8();
+                    characters.next();
+                }
+                (HALFWIDTH_KATAKANA, fullwidth.to_string())
+            }
+            '\u{3000}' if config.normalize.fullwidth_space == FullwidthSpace::ToAscii => {
+                (FULLWIDTH_SPACE, " ".to_string())
+            }
+            _ => continue,
+        };
+
+        match edits.last_mut() {
+            Some((last_code, last)) if *last_code == code && last.range.end == start => {
+                last.range.end = end;
+                last.replacement.push_str(&replacement);
+            }
+            _ => edits.push((
+                code,
+                TextEdit {
+                    range: start..end,
+                    replacement,
+                },
+            )),
+        }
+    }
+    edits.extend(punctuation_edits(config, text, excluded));
+    edits.sort_by_key(|(_, edit)| edit.range.start);
+    edits
+}
+
+/// Returns the message of a diagnostic reported for a normalization edit.
+pub(crate) fn normalize_message(code: &str) -> &'static str {
+    match code {
+        FULLWIDTH_ALPHANUMERICS => "Full-width alphanumerics found",
+        HALFWIDTH_KATAKANA => "Half-width katakana found",
+        FULLWIDTH_SPACE => "Full-width space found",
+        _ => punctuation_message(code),
+    }
+}
+
+fn fullwidth_katakana(c: char) -> char {
+    FULLWIDTH_KATAKANA
+        .chars()
+        .nth(c as usize - 0xFF61)
+        .unwrap_or(c)
+}
+
+fn combine_sound_mark(katakana: char, mark: char) -> Option<char> {
+    let (unmarked, marked) = match mark {
+        '\u{FF9E}' => (UNVOICED, VOICED),
+        '\u{FF9F}' => (UNSEMIVOICED, SEMIVOICED),
+        _ => return None,
+    };
+    let index = unmarked.chars().position(|c| c == katakana)?;
+    marked.chars().nth(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::config::Period;
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.normalize.fullwidth_alphanumerics = FullwidthAlphanumerics::ToHalfwidth;
+        config.normalize.halfwidth_katakana = HalfwidthKatakana::ToFullwidth;
+        config.normalize.fullwidth_space = FullwidthSpace::ToAscii;
+        config
+    }
+
+    fn edits(config: &Config, text: &str) -> Vec<(&'static str, Range<usize>, String)> {
+        normalize_edits(config, text, &[])
+            .into_iter()
+            .map(|(code, edit)| (code, edit.range, edit.replacement))
+            .collect()
+    }
+
+    #[test]
+    fn keep_leaves_every_character_unchanged() {
+        assert!(normalize_edits(&Config::default(), "ＡＢＣ１２３ｶﾀｶﾅ\u{3000}", &[]).is_empty());
+    }
+
+    #[rstest]
+    #[case("ＡＢＣは１２３", vec![("W005", 0..9, "ABC"), ("W005", 12..21, "123")])]
+    #[case("ｚ＿０", vec![("W005", 0..3, "z"), ("W005", 6..9, "0")])]
+    #[case("ｶﾀｶﾅ", vec![("W006", 0..12, "カタカナ")])]
+    #[case("ｶﾞｯﾂﾟﾎﾟｳﾞ", vec![("W006", 0..27, "ガッツ゜ポヴ")])]
+    #[case("ｰ｡ﾞ", vec![("W006", 0..9, "ー。゛")])]
+    #[case("漢\u{3000}\u{3000}字", vec![("W007", 3..9, "  ")])]
+    #[case("Ａｱ\u{3000}", vec![("W005", 0..3, "A"), ("W006", 3..6, "ア"), ("W007", 6..9, " ")])]
+    fn normalize_edits_replace_runs_of_characters(
+        #[case] text: &str,
+        #[case] expected: Vec<(&str, Range<usize>, &str)>,
+    ) {
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(code, range, replacement)| (code, range, replacement.to_string()))
+            .collect();
+        assert_eq!(edits(&config(), text), expected);
+    }
+
+    #[test]
+    fn punctuation_style_rules_take_precedence_over_halfwidth_katakana() {
+        let mut config = config();
+        config.punctuation.period = Period::Fullwidth;
+        assert_eq!(
+            edits(&config, "ｶﾅ｡､"),
+            [
+                ("W006", 0..6, "カナ".to_string()),
+                ("W008", 6..9, "．".to_string()),
+                ("W006", 9..12, "、".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rules_are_independent() {
+        let mut config = Config::default();
+        config.normalize.halfwidth_katakana = HalfwidthKatakana::ToFullwidth;
+        assert_eq!(
+            edits(&config, "Ａｱ\u{3000}"),
+            [("W006", 3..6, "ア".to_string())]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use cjkfmt_core::{diagnostic::Diagnostic, position::Position};
+use tree_sitter::Tree;
+
+use crate::{
+    config::Config,
+    document::Document,
+    markdown_spacing::markdown_prose_edits,
+    normalize::{normalize_edits, normalize_message},
+};
+
+/// Checks for full-width and half-width variants of characters and for
+/// punctuation in Markdown prose that the configuration requires to be
+/// normalized.
+///
+/// The diagnostics are reported exactly where the `format` command replaces
+/// characters.
+pub(crate) fn check_normalization(
+    config: &Config,
+    document: &Document,
+    tree: &Tree,
+) -> anyhow::Result<Vec<Diagnostic>> {
+    let edits = markdown_prose_edits(&document.content, tree, |text, exclusions| {
+        normalize_edits(config, text, exclusions)
+    })?;
+    Ok(edits
+        .into_iter()
+        .map(|(code, edit)| {
+            let start = Position::from_offset(&document.content, edit.range.start);
+            let end = Position::from_offset(&document.content, edit.range.end);
+            Diagnostic::new(
+                document.filename.as_deref(),
+                start,
+                end,
+                code.to_string(),
+                normalize_message(code).to_string(),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use cjkfmt_parser::Grammar;
+
+    use super::*;
+    use crate::config::{FullwidthAlphanumerics, FullwidthSpace};
+
+    #[test]
+    fn check_normalization_reports_only_markdown_prose() {
+        let mut config = Config::default();
+        config.normalize.fullwidth_alphanumerics = FullwidthAlphanumerics::ToHalfwidth;
+        config.normalize.fullwidth_space = FullwidthSpace::ToAscii;
+
+        let mut document = Document::new(
+            "# 見出し\n\n漢字\u{3000}ＡＢ `ＣＤ`\n",
+            Grammar::Markdown,
+            Some("t.md"),
+        );
+        document.parse().expect("failed to parse the document");
+
+        let diagnostics =
+            check_normalization(&config, &document, document.tree().unwrap()).unwrap();
+        let codes: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code.as_str(), diagnostic.start, diagnostic.end))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                ("W007", Position::new(2, 2), Position::new(2, 3)),
+                ("W005", Position::new(2, 3), Position::new(2, 5)),
+            ]
+        );
+    }
+}
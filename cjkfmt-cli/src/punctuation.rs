@@ -0,0 +1,248 @@
+//! Normalization of the style of punctuation.
+use std::ops::Range;
+
+use crate::{
+    config::{Comma, Config, Parentheses, Period},
+    spacing::{TextEdit, is_cjk},
+};
+
+/// Diagnostic code of full stops not matching `punctuation.period`.
+pub(crate) const PERIOD: &str = "W008";
+
+/// Diagnostic code of commas not matching `punctuation.comma`.
+pub(crate) const COMMA: &str = "W009";
+
+/// Diagnostic code of half-width parentheses around CJK text.
+pub(crate) const PARENTHESES: &str = "W010";
+
+/// Full stops replaced by each other, including the half-width `｡`.
+const FULL_STOPS: [char; 3] = ['。', '．', '｡'];
+
+/// Commas replaced by each other, including the half-width `､`.
+const COMMAS: [char; 3] = ['、', '，', '､'];
+
+/// Returns the punctuation style edits needed for a piece of text, each with
+/// the diagnostic code of the rule requiring it.
+///
+/// A full stop or a comma between digits is left alone, as it is a decimal
+/// point or a thousands separator as in `３．１４` and `１，０００`.
+///
+/// Characters in the `excluded` byte ranges, such as code spans, are not
+/// prose and are neither replaced nor paired with parentheses outside of them.
+/// The ranges are UTF-8 byte ranges relative to `text`, in order.
+pub(crate) fn punctuation_edits(
+    config: &Config,
+    text: &str,
+    excluded: &[Range<usize>],
+) -> Vec<(&'static str, TextEdit)> {
+    let mut edits = Vec::new();
+    let characters: Vec<(usize, char)> = text
+        .char_indices()
+        .map(|(start, c)| {
+            if excluded.iter().any(|range| range.contains(&start)) {
+                (start, '\u{FFFC}')
+            } else {
+                (start, c)
+            }
+        })
+        .collect();
+    let mut openings: Vec<usize> = Vec::new();
+    for (index, &(start, c)) in characters.iter().enumerate() {
+        if let Some((code, replacement)) = replacement(config, c) {
+            let before = index.checked_sub(1).map(|before| characters[before].1);
+            let after = characters.get(index + 1).map(|&(_, after)| after);
+            if !(before.is_some_and(is_digit) && after.is_some_and(is_digit)) {
+                edits.push((code, edit(start, c, replacement)));
+            }
+            continue;
+        }
+        if config.punctuation.parentheses != Parentheses::FullwidthAroundCjk {
+            continue;
+        }
+
+        match c {
+            '(' => openings.push(index),
+            ')' => {
+                let Some(opening) = openings.pop() else {
+                    continue;
+                };
+
+                // Parentheses are around CJK text if they enclose some or if
+                // either of them adjoins some from outside.
+                let before = opening.checked_sub(1).map(|before| characters[before].1);
+                let after = characters.get(index + 1).map(|&(_, after)| after);
+                let around_cjk = before
+                    .into_iter()
+                    .chain(characters[opening + 1..index].iter().map(|&(_, c)| c))
+                    .chain(after)
+                    .any(|c| is_cjk(config, c));
+                if around_cjk {
+                    edits.push((PARENTHESES, edit(characters[opening].0, '(', '（')));
+                    edits.push((PARENTHESES, edit(start, ')', '）')));
+                }
+            }
+            _ => {}
+        }
+    }
+    edits.sort_by_key(|(_, edit)| edit.range.start);
+    edits
+}
+
+/// Returns whether a punctuation style rule replaces a character by itself.
+pub(crate) fn replaces(config: &Config, c: char) -> bool {
+    replacement(config, c).is_some()
+}
+
+/// Returns the message of a diagnostic reported for a punctuation style edit.
+pub(crate) fn punctuation_message(code: &str) -> &'static str {
+    match code {
+        PERIOD => "Full stop does not match `punctuation.period`",
+        COMMA => "Comma does not match `punctuation.comma`",
+        _ => "Half-width parentheses around CJK text found",
+    }
+}
+
+fn replacement(config: &Config, c: char) -> Option<(&'static str, char)> {
+    let (code, replacement) = if FULL_STOPS.contains(&c) {
+        match config.punctuation.period {
+            Period::Keep => return None,
+            Period::Ideographic => (PERIOD, '。'),
+            Period::Fullwidth => (PERIOD, '．'),
+        }
+    } else if COMMAS.contains(&c) {
+        match config.punctuation.comma {
+            Comma::Keep => return None,
+            Comma::Ideographic => (COMMA, '、'),
+            Comma::Fullwidth => (COMMA, '，'),
+        }
+    } else {
+        return None;
+    };
+    (c != replacement).then_some((code, replacement))
+}
+
+/// Returns whether the character is a half-width or full-width decimal digit.
+fn is_digit(c: char) -> bool {
+    c.is_ascii_digit() || ('０'..='９').contains(&c)
+}
+
+fn edit(start: usize, c: char, replacement: char) -> TextEdit {
+    TextEdit {
+        range: start..start + c.len_utf8(),
+        replacement: replacement.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn config(period: Period, comma: Comma, parentheses: Parentheses) -> Config {
+        let mut config = Config::default();
+        config.punctuation.period = period;
+        config.punctuation.comma = comma;
+        config.punctuation.parentheses = parentheses;
+        config
+    }
+
+    fn edits(config: &Config, text: &str) -> Vec<(&'static str, Range<usize>, String)> {
+        punctuation_edits(config, text, &[])
+            .into_iter()
+            .map(|(code, edit)| (code, edit.range, edit.replacement))
+            .collect()
+    }
+
+    #[test]
+    fn keep_leaves_every_character_unchanged() {
+        assert!(punctuation_edits(&Config::default(), "漢字，かな．カナ(仮名)｡", &[]).is_empty());
+    }
+
+    #[rstest]
+    #[case(Period::Ideographic, Comma::Ideographic, "Ａ，Ｂ．Ｃ､Ｄ｡", vec![
+        ("W009", 3..6, "、"),
+        ("W008", 9..12, "。"),
+        ("W009", 15..18, "、"),
+        ("W008", 21..24, "。"),
+    ])]
+    #[case(Period::Fullwidth, Comma::Fullwidth, "漢、字。", vec![
+        ("W009", 3..6, "，"),
+        ("W008", 9..12, "．"),
+    ])]
+    #[case(Period::Ideographic, Comma::Keep, "漢、字，。", vec![])]
+    #[case(Period::Keep, Comma::Fullwidth, "。．", vec![])]
+    #[case(Period::Ideographic, Comma::Ideographic, "１．５と１，０００と1.5と1,000", vec![])]
+    #[case(Period::Ideographic, Comma::Ideographic, "１，０００円で、３．１４です．", vec![
+        ("W008", 42..45, "。"),
+    ])]
+    #[case(Period::Fullwidth, Comma::Fullwidth, "１。５と、", vec![
+        ("W009", 12..15, "，"),
+    ])]
+    fn replaces_full_stops_and_commas(
+        #[case] period: Period,
+        #[case] comma: Comma,
+        #[case] text: &str,
+        #[case] expected: Vec<(&str, Range<usize>, &str)>,
+    ) {
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(code, range, replacement)| (code, range, replacement.to_string()))
+            .collect();
+        let config = config(period, comma, Parentheses::Keep);
+        assert_eq!(edits(&config, text), expected);
+    }
+
+    #[rstest]
+    #[case("東京(とうきょう)", Some(6..7), Some(22..23))]
+    #[case("東京(Tokyo)", Some(6..7), Some(12..13))]
+    #[case("(注)を参照", Some(0..1), Some(4..5))]
+    #[case("Tokyo(東京)", Some(5..6), Some(12..13))]
+    #[case("f(x) を", None, None)]
+    #[case("関数 f(x) を", None, None)]
+    #[case("漢字 (x)", None, None)]
+    #[case("漢字(x", None, None)]
+    fn replaces_parentheses_around_cjk_text(
+        #[case] text: &str,
+        #[case] opening: Option<Range<usize>>,
+        #[case] closing: Option<Range<usize>>,
+    ) {
+        let config = config(Period::Keep, Comma::Keep, Parentheses::FullwidthAroundCjk);
+        let expected: Vec<_> = opening
+            .map(|range| (PARENTHESES, range, "（".to_string()))
+            .into_iter()
+            .chain(closing.map(|range| (PARENTHESES, range, "）".to_string())))
+            .collect();
+        assert_eq!(edits(&config, text), expected);
+    }
+
+    #[test]
+    fn nested_parentheses_are_replaced_independently() {
+        let config = config(Period::Keep, Comma::Keep, Parentheses::FullwidthAroundCjk);
+        let ranges: Vec<_> = edits(&config, "a(b(漢)c) d(e)")
+            .into_iter()
+            .map(|(_, range, _)| range)
+            .collect();
+        assert_eq!(ranges, [1..2, 3..4, 7..8, 9..10]);
+    }
+
+    #[test]
+    fn excluded_characters_are_neither_replaced_nor_paired() {
+        let config = config(
+            Period::Ideographic,
+            Comma::Keep,
+            Parentheses::FullwidthAroundCjk,
+        );
+        let code_span = 4..10;
+        let edits: Vec<_> = punctuation_edits(&config, "漢(`．)`)字", &[code_span])
+            .into_iter()
+            .map(|(_, edit)| (edit.range, edit.replacement))
+            .collect();
+        assert_eq!(
+            edits,
+            [(3..4, "（".to_string()), (10..11, "）".to_string())]
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use std::{ops::Range, str::CharIndices};
 
 use unicode_general_category::{GeneralCategory, get_general_category};
+use unicode_width::UnicodeWidthChar;
 
-use crate::config::{Config, SpacingRule};
+use crate::config::{AmbiguousWidth, Config, SpacingRule};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct TextEdit {
@@ -25,7 +26,7 @@ enum CharType {
 /// intentionally not considered here; callers that understand a syntax tree
 /// filter these edits before applying them.
 pub(crate) fn spacing_edits(config: &Config, text: &str) -> Vec<TextEdit> {
-    let characters = text_characters(text);
+    let characters = text_characters(config, text);
     let mut edits = Vec::new();
 
     for pair in characters.windows(2) {
@@ -79,6 +80,51 @@ pub(crate) fn spacing_edits(config: &Config, text: &str) -> Vec<TextEdit> {
     edits
 }
 
+/// Returns the separator to put between two characters when a soft line
+/// break between them is removed.
+///
+/// Wide characters such as CJK ideographs and kana are joined directly, as
+/// are pairs whose spacing is prohibited by the configuration. Everything
+/// else is joined with a single ASCII space.
+pub(crate) fn join_separator(config: &Config, left: char, right: char) -> &'static str {
+    if is_wide(config, left) && is_wide(config, right) {
+        return "";
+    }
+
+    let (left, right) = (char_type(config, left), char_type(config, right));
+    if is_spacing_pair(left, right) && spacing_rule(config, left, right) == SpacingRule::Prohibit {
+        ""
+    } else {
+        " "
+    }
+}
+
+/// Returns the spacing rule between two adjacent characters, or `None` if
+/// they do not form a spacing pair.
+pub(crate) fn pair_rule(config: &Config, left: char, right: char) -> Option<SpacingRule> {
+    let (left, right) = (char_type(config, left), char_type(config, right));
+    is_spacing_pair(left, right).then(|| spacing_rule(config, left, right))
+}
+
+/// Returns whether a character counts as a CJK character in the spacing rules.
+pub(crate) fn is_cjk(config: &Config, c: char) -> bool {
+    char_type(config, c) == CharType::Cjk
+}
+
+/// Returns whether a character is displayed wide.
+///
+/// East Asian Ambiguous symbols such as `…` and `※` are wide unless
+/// `ambiguous_width` is `narrow`, as in the line breaker. Ambiguous letters
+/// such as Greek and Cyrillic ones are always narrow.
+pub(crate) fn is_wide(config: &Config, c: char) -> bool {
+    match config.ambiguous_width {
+        AmbiguousWidth::Narrow => c.width() == Some(2),
+        AmbiguousWidth::Wide => {
+            c.width() == Some(2) || (c.width_cjk() == Some(2) && !c.is_alphanumeric())
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct TextCharacter {
     start: usize,
@@ -87,7 +133,7 @@ struct TextCharacter {
     kind: CharType,
 }
 
-fn text_characters(text: &str) -> Vec<TextCharacter> {
+fn text_characters(config: &Config, text: &str) -> Vec<TextCharacter> {
     let mut characters = Vec::new();
     let mut indices: CharIndices<'_> = text.char_indices();
     while let Some((start, character)) = indices.next() {
@@ -99,7 +145,7 @@ fn text_characters(text: &str) -> Vec<TextCharacter> {
             start,
             end,
             character,
-            kind: char_type(character),
+            kind: char_type(config, character),
         });
     }
     characters
@@ -127,7 +173,7 @@ fn spacing_rule(config: &Config, left: CharType, right: CharType) -> SpacingRule
     }
 }
 
-fn char_type(c: char) -> CharType {
+fn char_type(config: &Config, c: char) -> CharType {
     // Only ASCII spaces are editable. Other whitespace must also prevent a
     // spacing pair from spanning it, including U+3000 in the broad CJK range.
     match c {
@@ -136,6 +182,43 @@ fn char_type(c: char) -> CharType {
         _ => {}
     }
 
+    // Characters the configuration puts in a class belong to it, even if they
+    // belong to another class by default.
+    let classes = &config.spacing.classes;
+    let configured = [
+        (CharType::Cjk, &classes.cjk),
+        (CharType::Latin, &classes.latin),
+        (CharType::Digit, &classes.digit),
+    ];
+    if let Some((kind, _)) = configured
+        .iter()
+        .find(|(_, members)| members.as_ref().is_some_and(|members| members.includes(c)))
+    {
+        return *kind;
+    }
+
+    let kind = default_char_type(config, c);
+    let members = match kind {
+        CharType::Cjk => &classes.cjk,
+        CharType::Latin => &classes.latin,
+        CharType::Digit => &classes.digit,
+        CharType::Space | CharType::Other => return kind,
+    };
+    if members.as_ref().is_some_and(|members| members.excludes(c)) {
+        CharType::Other
+    } else {
+        kind
+    }
+}
+
+/// Returns the class of a character described in `docs/design/spacing.md`.
+fn default_char_type(config: &Config, c: char) -> CharType {
+    // Full-width punctuation such as `（`, `」`, and `。` may count as CJK,
+    // wherever it is encoded.
+    if config.spacing.punctuation_as_fullwidth && is_punctuation(c) && is_wide(config, c) {
+        return CharType::Cjk;
+    }
+
     // TODO: Refine the character set by reviewing https://www.unicode.org/charts/
     match c {
         // CJK Unified Ideographs
@@ -169,17 +252,14 @@ fn char_type(c: char) -> CharType {
         // Bopomofo: U+3100–U+312F
         | '\u{3100}'..='\u{312F}'
         // Hangul Syllables: U+AC00–U+D7AF
-        | '\u{AC00}'..='\u{D7AF}' => match get_general_category(c) {
+        | '\u{AC00}'..='\u{D7AF}' => {
             // Exclude punctuation characters.
-            GeneralCategory::ClosePunctuation
-            | GeneralCategory::ConnectorPunctuation
-            | GeneralCategory::DashPunctuation
-            | GeneralCategory::FinalPunctuation
-            | GeneralCategory::InitialPunctuation
-            | GeneralCategory::OpenPunctuation
-            | GeneralCategory::OtherPunctuation => CharType::Other,
-            _ => CharType::Cjk,
-        },
+            if is_punctuation(c) {
+                CharType::Other
+            } else {
+                CharType::Cjk
+            }
+        }
 
         // Basic Latin : Uppercase and lowercase letters
         'A'..='Z'
@@ -220,6 +300,19 @@ fn char_type(c: char) -> CharType {
     }
 }
 
+fn is_punctuation(c: char) -> bool {
+    matches!(
+        get_general_category(c),
+        GeneralCategory::ClosePunctuation
+            | GeneralCategory::ConnectorPunctuation
+            | GeneralCategory::DashPunctuation
+            | GeneralCategory::FinalPunctuation
+            | GeneralCategory::InitialPunctuation
+            | GeneralCategory::OpenPunctuation
+            | GeneralCategory::OtherPunctuation
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,14 +381,115 @@ mod tests {
         assert!(spacing_edits(&config, "漢A 漢 1").is_empty());
     }
 
+    #[test]
+    fn join_separator_depends_on_both_sides() {
+        let config = make_config(SpacingRule::Ignore, SpacingRule::Ignore);
+        assert_eq!(join_separator(&config, '漢', 'あ'), "");
+        assert_eq!(join_separator(&config, '。', 'あ'), "");
+        assert_eq!(join_separator(&config, 'a', 'b'), " ");
+        assert_eq!(join_separator(&config, '漢', 'A'), " ");
+        assert_eq!(join_separator(&config, '.', '漢'), " ");
+
+        let config = make_config(SpacingRule::Prohibit, SpacingRule::Require);
+        assert_eq!(join_separator(&config, '漢', 'A'), "");
+        assert_eq!(join_separator(&config, '漢', '1'), " ");
+    }
+
     #[test]
     fn character_types_keep_punctuation_out_of_spacing_pairs() {
-        assert_eq!(char_type('中'), CharType::Cjk);
-        assert_eq!(char_type('漢'), CharType::Cjk);
-        assert_eq!(char_type('a'), CharType::Latin);
-        assert_eq!(char_type('1'), CharType::Digit);
-        assert_eq!(char_type(' '), CharType::Space);
-        assert_eq!(char_type('。'), CharType::Other);
-        assert_eq!(char_type('\u{3000}'), CharType::Other);
+        let config = Config::default();
+        assert_eq!(char_type(&config, '中'), CharType::Cjk);
+        assert_eq!(char_type(&config, '漢'), CharType::Cjk);
+        assert_eq!(char_type(&config, 'a'), CharType::Latin);
+        assert_eq!(char_type(&config, '1'), CharType::Digit);
+        assert_eq!(char_type(&config, ' '), CharType::Space);
+        assert_eq!(char_type(&config, '。'), CharType::Other);
+        assert_eq!(char_type(&config, '（'), CharType::Other);
+        assert_eq!(char_type(&config, '\u{3000}'), CharType::Other);
+    }
+
+    #[test]
+    fn character_types_count_full_width_punctuation_as_cjk_if_configured() {
+        let mut config = Config::default();
+        config.spacing.punctuation_as_fullwidth = true;
+        for c in ['（', '）', '「', '」', '。', '、', '！', '・'] {
+            assert_eq!(char_type(&config, c), CharType::Cjk, "{c:?}");
+        }
+        for c in ['(', '.', '｢', '\u{3000}', '＄', 'Ａ'] {
+            assert_eq!(char_type(&config, c), CharType::Other, "{c:?}");
+        }
+    }
+
+    #[test]
+    fn punctuation_as_fullwidth_pairs_punctuation_with_latin_and_digits() {
+        let mut config = make_config(SpacingRule::Require, SpacingRule::Prohibit);
+        assert!(spacing_edits(&config, "「A」（1 ）").is_empty());
+
+        config.spacing.punctuation_as_fullwidth = true;
+        assert_eq!(
+            edits(&config, "「A」（1 ）"),
+            vec![
+                (3..3, " ".to_string()),
+                (4..4, " ".to_string()),
+                (11..12, String::new())
+            ]
+        );
+        assert_eq!(join_separator(&config, '。', 'A'), " ");
+        config.spacing.alphabets = SpacingRule::Prohibit;
+        assert_eq!(join_separator(&config, '。', 'A'), "");
+    }
+
+    fn classes_config(json: &str) -> Config {
+        use figment::{
+            Figment,
+            providers::{Format, Json, Serialized},
+        };
+
+        Figment::new()
+            .merge(Serialized::defaults(Config::default()))
+            .merge(Json::string(json))
+            .extract()
+            .expect("the character classes should deserialize")
+    }
+
+    #[test]
+    fn configured_classes_add_and_remove_characters() {
+        let config = classes_config(
+            r#"{
+                "spacing": {
+                    "classes": {
+                        "cjk": { "add": ["U+1100..U+11FF"] },
+                        "latin": { "add": ["script:Greek", "script:Cyrl"], "remove": ["×÷"] },
+                        "digit": { "add": ["U+FF10..U+FF19", "一二三"] }
+                    }
+                }
+            }"#,
+        );
+        assert_eq!(char_type(&config, '\u{1100}'), CharType::Cjk);
+        assert_eq!(char_type(&config, 'α'), CharType::Latin);
+        assert_eq!(char_type(&config, 'Я'), CharType::Latin);
+        assert_eq!(char_type(&config, '×'), CharType::Other);
+        assert_eq!(char_type(&config, 'é'), CharType::Latin);
+        assert_eq!(char_type(&config, '５'), CharType::Digit);
+        assert_eq!(char_type(&config, '一'), CharType::Digit);
+        assert_eq!(char_type(&config, '漢'), CharType::Cjk);
+        assert_eq!(char_type(&config, ' '), CharType::Space);
+    }
+
+    #[test]
+    fn configured_classes_replace_the_default_characters() {
+        let config = classes_config(r#"{ "spacing": { "classes": { "cjk": ["script:Han"] } } }"#);
+        assert_eq!(char_type(&config, '漢'), CharType::Cjk);
+        assert_eq!(char_type(&config, 'あ'), CharType::Other);
+        assert_eq!(char_type(&config, 'A'), CharType::Latin);
+    }
+
+    #[test]
+    fn configured_classes_form_spacing_pairs() {
+        let mut config = classes_config(
+            r#"{ "spacing": { "classes": { "latin": { "add": ["script:Greek"] } } } }"#,
+        );
+        config.spacing.alphabets = SpacingRule::Require;
+        assert_eq!(edits(&config, "漢α"), vec![(3..3, " ".to_string())]);
     }
 }
@@ -5,6 +5,7 @@ use unicode_segmentation::UnicodeSegmentation;
 use crate::{
     config::Config,
     document::Document,
+    markdown_spacing::markup_boundary_edits,
     spacing::{TextEdit, spacing_edits},
 };
 
@@ -14,6 +15,7 @@ pub struct SpacingChecker<'a> {
     config: &'a Config,
     document: &'a Document,
     diagnostics: Vec<Diagnostic>,
+    edits: Vec<TextEdit>,
 }
 
 impl<'a> SpacingChecker<'a> {
@@ -23,6 +25,7 @@ impl<'a> SpacingChecker<'a> {
             config,
             document,
             diagnostics: Vec::new(),
+            edits: Vec::new(),
         }
     }
 
@@ -30,6 +33,13 @@ impl<'a> SpacingChecker<'a> {
     pub fn diagnostics(&self) -> &[Diagnostic] {
         &self.diagnostics
     }
+
+    /// Returns the edits fixing each of the collected diagnostics, in the same order.
+    ///
+    /// The ranges of the edits are byte offsets in the whole document.
+    pub(crate) fn edits(&self) -> &[TextEdit] {
+        &self.edits
+    }
 }
 
 /// Implements the NodeVisitor trait to traverse the parse tree and check for spacing issues.
@@ -44,11 +54,18 @@ impl<'a> NodeVisitor for SpacingChecker<'a> {
             // Convert the shared spacing edits into diagnostics. The checker
             // deliberately retains its existing block-inline scope; Markdown
             // prose filtering belongs to the formatter's Markdown module.
-            for edit in spacing_edits(self.config, text) {
+            let mut edits = spacing_edits(self.config, text);
+            edits.extend(markup_boundary_edits(self.config, text));
+            edits.sort_by_key(|edit| edit.range.start);
+            for edit in edits {
                 let absolute_start = range_start + edit.range.start;
                 let absolute_end = range_start + edit.range.end;
                 let diagnostic = self.diagnostic_for_edit(&edit, absolute_start, absolute_end);
                 self.diagnostics.push(diagnostic);
+                self.edits.push(TextEdit {
+                    range: absolute_start..absolute_end,
+                    replacement: edit.replacement,
+                });
             }
         }
     }
@@ -64,33 +81,24 @@ impl<'a> SpacingChecker<'a> {
         absolute_start: usize,
         absolute_end: usize,
     ) -> Diagnostic {
-        let text_before = &self.document.content[..absolute_start];
-        let line_index = text_before.chars().filter(|&c| c == '\n').count() as u32;
-        let line_start = text_before
-            .rsplit_once('\n')
-            .map(|(_, line)| line)
-            .unwrap_or(text_before);
-        let column_index = utf16_len(line_start);
+        let content = &self.document.content;
 
-        let end_column = if edit.range.is_empty() {
-            self.document.content[absolute_start..]
+        // An insertion is reported on the grapheme following it.
+        let end = if edit.range.is_empty() {
+            content[absolute_start..]
                 .graphemes(true)
                 .next()
-                .map_or(column_index, |grapheme| column_index + utf16_len(grapheme))
+                .map_or(absolute_start, |grapheme| absolute_start + grapheme.len())
         } else {
-            column_index + utf16_len(&self.document.content[absolute_start..absolute_end])
+            absolute_end
         };
 
         Diagnostic::new(
             self.document.filename.as_deref(),
-            Position::new(line_index, column_index),
-            Position::new(line_index, end_column),
+            Position::from_offset(content, absolute_start),
+            Position::from_offset(content, end),
             "W002".to_string(),
             "Possible spacing position found".to_string(),
         )
     }
 }
-
-fn utf16_len(text: &str) -> u32 {
-    text.encode_utf16().count() as u32
-}
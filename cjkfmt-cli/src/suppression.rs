@@ -0,0 +1,273 @@
+//! Inline suppression comments in Markdown documents.
+//!
+//! Suppressions are written as HTML comments, either as a block of their own
+//! or inside paragraph text:
+//!
+//! - `<!-- cjkfmt-disable -->` suppresses diagnostics from its line until a
+//!   `<!-- cjkfmt-enable -->` comment or the end of the document.
+//! - `<!-- cjkfmt-disable-next-line -->` suppresses diagnostics on the line
+//!   following the comment.
+//! - `<!-- cjkfmt-ignore-file -->` suppresses every diagnostic in the document
+//!   and leaves it unformatted.
+//!
+//! The first three directives may be followed by diagnostic codes separated by
+//! spaces or commas (e.g. `<!-- cjkfmt-disable W001 -->`) to affect only them.
+use std::ops::Range;
+
+use cjkfmt_parser::{Grammar, parse};
+use tree_sitter::{Node, Tree};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Directive {
+    Disable,
+    Enable,
+    DisableNextLine,
+    IgnoreFile,
+}
+
+/// A `disable` or `enable` directive, which stays in effect until the next one.
+#[derive(Debug)]
+struct Toggle {
+    line: u32,
+    disable: bool,
+    codes: Vec<String>,
+}
+
+/// A `disable-next-line` directive.
+#[derive(Debug)]
+struct NextLine {
+    line: u32,
+    codes: Vec<String>,
+}
+
+/// Suppression directives found in a Markdown document.
+#[derive(Debug, Default)]
+pub(crate) struct Suppressions {
+    ignore_file: bool,
+    toggles: Vec<Toggle>,
+    next_lines: Vec<NextLine>,
+
+    /// Byte offset of the start of each line in the document.
+    line_starts: Vec<usize>,
+}
+
+impl Suppressions {
+    /// Collects the suppression directives from a document and its Markdown CST.
+    ///
+    /// Directives are searched for in `html_block` nodes and in the `html_tag`
+    /// nodes of paragraph text. Trees of other grammars have no such nodes, so
+    /// nothing is suppressed in them.
+    pub(crate) fn new(source: &str, tree: &Tree) -> anyhow::Result<Self> {
+        let mut comments = Vec::new();
+        collect_comments(source, tree.root_node(), &mut comments)?;
+
+        let mut suppressions = Suppressions {
+            line_starts: std::iter::once(0)
+                .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+                .collect(),
+            ..Suppressions::default()
+        };
+        for range in comments {
+            let Some((directive, codes)) = parse_directive(&source[range.clone()]) else {
+                continue;
+            };
+            let start_line = suppressions.line_of(range.start);
+            let end_line = suppressions.line_of(range.end.saturating_sub(1));
+            match directive {
+                Directive::Disable | Directive::Enable => suppressions.toggles.push(Toggle {
+                    line: start_line,
+                    disable: directive == Directive::Disable,
+                    codes,
+                }),
+                Directive::DisableNextLine => suppressions.next_lines.push(NextLine {
+                    line: end_line + 1,
+                    codes,
+                }),
+                Directive::IgnoreFile => suppressions.ignore_file = true,
+            }
+        }
+        Ok(suppressions)
+    }
+
+    /// Returns `true` if the document has a `cjkfmt-ignore-file` directive.
+    pub(crate) fn ignores_file(&self) -> bool {
+        self.ignore_file
+    }
+
+    /// Returns `true` if diagnostics with `code` are suppressed on the zero-based `line`.
+    pub(crate) fn is_suppressed(&self, line: u32, code: &str) -> bool {
+        if self.ignore_file {
+            return true;
+        }
+        if self
+            .next_lines
+            .iter()
+            .any(|next_line| next_line.line == line && applies_to(&next_line.codes, code))
+        {
+            return true;
+        }
+
+        // The last directive at or before the line that applies to the code wins.
+        self.toggles
+            .iter()
+            .take_while(|toggle| toggle.line <= line)
+            .filter(|toggle| applies_to(&toggle.codes, code))
+            .last()
+            .is_some_and(|toggle| toggle.disable)
+    }
+
+    /// Returns `true` if diagnostics with `code` are suppressed on the line
+    /// containing the byte `offset`.
+    pub(crate) fn is_suppressed_at(&self, offset: usize, code: &str) -> bool {
+        self.is_suppressed(self.line_of(offset), code)
+    }
+
+    fn line_of(&self, offset: usize) -> u32 {
+        (self.line_starts.partition_point(|&start| start <= offset) - 1) as u32
+    }
+}
+
+/// Returns `true` if a directive listing `codes` applies to `code`.
+///
+/// A directive without codes applies to every code.
+fn applies_to(codes: &[String], code: &str) -> bool {
+    codes.is_empty() || codes.iter().any(|c| c == code)
+}
+
+/// Parses an HTML comment as a suppression directive and its codes.
+fn parse_directive(text: &str) -> Option<(Directive, Vec<String>)> {
+    let body = text.trim().strip_prefix("<!--")?.strip_suffix("-->")?;
+    let mut words = body
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty());
+    let directive = match words.next()? {
+        "cjkfmt-disable" => Directive::Disable,
+        "cjkfmt-enable" => Directive::Enable,
+        "cjkfmt-disable-next-line" => Directive::DisableNextLine,
+        "cjkfmt-ignore-file" => Directive::IgnoreFile,
+        _ => return None,
+    };
+    Some((directive, words.map(str::to_string).collect()))
+}
+
+/// Collects the byte ranges of HTML comments in document order.
+fn collect_comments(
+    source: &str,
+    node: Node<'_>,
+    comments: &mut Vec<Range<usize>>,
+) -> anyhow::Result<()> {
+    match node.kind() {
+        "html_block" => comments.push(node.byte_range()),
+        "inline" => {
+            // Most paragraphs have no comment, so avoid parsing them again.
+            let start = node.start_byte();
+            let text = &source[node.byte_range()];
+            if text.contains("<!--") {
+                let inline_tree = parse(Grammar::MarkdownInline, text)?;
+                collect_html_tags(inline_tree.root_node(), start, comments);
+            }
+        }
+        _ => {
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                collect_comments(source, child, comments)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn collect_html_tags(node: Node<'_>, offset: usize, comments: &mut Vec<Range<usize>>) {
+    if node.kind() == "html_tag" {
+        comments.push((offset + node.start_byte())..(offset + node.end_byte()));
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_html_tags(child, offset, comments);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suppressions(source: &str) -> Suppressions {
+        let tree = parse(Grammar::Markdown, source).unwrap();
+        Suppressions::new(source, &tree).unwrap()
+    }
+
+    #[test]
+    fn disable_lasts_until_enable() {
+        let suppressions = suppressions(concat!(
+            "a\n",
+            "<!-- cjkfmt-disable -->\n",
+            "b\n",
+            "<!-- cjkfmt-enable -->\n",
+            "c\n",
+        ));
+        let suppressed: Vec<bool> = (0..5)
+            .map(|line| suppressions.is_suppressed(line, "W001"))
+            .collect();
+        assert_eq!(suppressed, [false, true, true, false, false]);
+    }
+
+    #[test]
+    fn directives_can_be_limited_to_codes() {
+        let suppressions = suppressions(concat!(
+            "<!-- cjkfmt-disable W001, W002 -->\n",
+            "a\n",
+            "<!-- cjkfmt-enable W002 -->\n",
+            "b\n",
+        ));
+        assert!(suppressions.is_suppressed(1, "W001"));
+        assert!(suppressions.is_suppressed(1, "W002"));
+        assert!(suppressions.is_suppressed(3, "W001"));
+        assert!(!suppressions.is_suppressed(3, "W002"));
+    }
+
+    #[test]
+    fn disable_next_line_affects_only_the_following_line() {
+        let suppressions = suppressions(concat!(
+            "<!-- cjkfmt-disable-next-line W001 -->\n",
+            "a\n",
+            "b\n",
+        ));
+        assert!(suppressions.is_suppressed(1, "W001"));
+        assert!(!suppressions.is_suppressed(1, "W002"));
+        assert!(!suppressions.is_suppressed(2, "W001"));
+    }
+
+    #[test]
+    fn directives_are_found_inside_paragraphs() {
+        let source = "> A <!-- cjkfmt-disable-next-line -->\n> B\n> C\n";
+        let suppressions = suppressions(source);
+        assert!(suppressions.is_suppressed(1, "W002"));
+        assert!(!suppressions.is_suppressed(2, "W002"));
+        assert!(suppressions.is_suppressed_at(source.find('B').unwrap(), "W002"));
+        assert!(!suppressions.is_suppressed_at(source.find('C').unwrap(), "W002"));
+    }
+
+    #[test]
+    fn ignore_file_suppresses_everything() {
+        let suppressions = suppressions("a\n\n<!-- cjkfmt-ignore-file -->\n");
+        assert!(suppressions.ignores_file());
+        assert!(suppressions.is_suppressed(0, "W001"));
+    }
+
+    #[test]
+    fn other_comments_and_code_are_not_directives() {
+        let suppressions = suppressions(concat!(
+            "<!-- cjkfmt-unknown -->\n",
+            "<!-- note: cjkfmt-disable -->\n",
+            "\n",
+            "`<!-- cjkfmt-disable -->`\n",
+            "\n",
+            "```\n",
+            "<!-- cjkfmt-disable -->\n",
+            "```\n",
+            "a\n",
+        ));
+        assert!(!suppressions.is_suppressed(8, "W001"));
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 /// A position in a text document.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Position {
     /// Zero-based line number of where the issue was detected.
     pub line: u32,
@@ -20,4 +20,42 @@ impl Position {
     pub fn new(line: u32, column: u32) -> Self {
         Self { line, column }
     }
+
+    /// Returns the position of a UTF-8 byte offset in `text`.
+    ///
+    /// Lines are separated by `\n`, so a `\r` before it is counted as the last
+    /// column of its line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is not on a character boundary of `text`.
+    pub fn from_offset(text: &str, offset: usize) -> Self {
+        let before = &text[..offset];
+        let line = before.matches('\n').count() as u32;
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let column = before[line_start..].encode_utf16().count() as u32;
+        Self::new(line, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(0, Position::new(0, 0))]
+    #[case(3, Position::new(0, 1))]
+    #[case(7, Position::new(0, 3))]
+    #[case(8, Position::new(0, 4))]
+    #[case(9, Position::new(0, 5))]
+    #[case(10, Position::new(1, 0))]
+    #[case(11, Position::new(1, 1))]
+    fn from_offset_counts_columns_in_utf16_code_units(
+        #[case] offset: usize,
+        #[case] expected: Position,
+    ) {
+        assert_eq!(Position::from_offset("漢𠮷a\r\nb", offset), expected);
+    }
 }
@@ -10,13 +10,26 @@ pub enum Grammar {
 
 /// Infers the grammar type from the file extension of the given path.
 ///
-/// Only an exact lowercase `.json` extension selects JSON. All other paths
-/// retain the historical Markdown fallback used by the CLI commands.
+/// This is [`detect_grammar`] with the historical Markdown fallback used by
+/// the CLI commands for files with an unknown extension.
 pub fn grammar_from_path<P: AsRef<Path>>(path: P) -> Grammar {
-    let path = path.as_ref();
-    match path.extension().map(|s| s.to_str().unwrap()) {
-        Some("json") => Grammar::Json,
-        _ => Grammar::Markdown,
+    detect_grammar(path).unwrap_or(Grammar::Markdown)
+}
+
+/// Detects the grammar of a file from its extension, if it is supported.
+///
+/// Unlike [`grammar_from_path`], this returns `None` for files with an unknown
+/// extension. It is used to select files to process when walking directories:
+/// `.json` selects JSON, and `.md` or `.markdown` (in any letter case) selects
+/// Markdown.
+pub fn detect_grammar<P: AsRef<Path>>(path: P) -> Option<Grammar> {
+    let extension = path.as_ref().extension()?.to_str()?;
+    if extension == "json" {
+        Some(Grammar::Json)
+    } else if extension.eq_ignore_ascii_case("md") || extension.eq_ignore_ascii_case("markdown") {
+        Some(Grammar::Markdown)
+    } else {
+        None
     }
 }
 
@@ -45,4 +58,20 @@ mod tests {
             assert_eq!(grammar_from_path(path), Grammar::Markdown, "{path}");
         }
     }
+
+    #[test]
+    fn detects_only_supported_extensions() {
+        assert_eq!(detect_grammar("config.json"), Some(Grammar::Json));
+        assert_eq!(detect_grammar("README.md"), Some(Grammar::Markdown));
+        assert_eq!(detect_grammar("guide.MarkDown"), Some(Grammar::Markdown));
+        for path in [
+            "config.JSON",
+            "notes.txt",
+            "main.rs",
+            "README",
+            "README.md.txt",
+        ] {
+            assert_eq!(detect_grammar(path), None, "{path}");
+        }
+    }
 }
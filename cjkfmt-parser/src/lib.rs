@@ -4,6 +4,6 @@ mod grammar;
 mod node_visitor;
 mod parse;
 
-pub use grammar::{Grammar, grammar_from_path};
+pub use grammar::{Grammar, detect_grammar, grammar_from_path};
 pub use node_visitor::NodeVisitor;
 pub use parse::parse;